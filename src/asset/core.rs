@@ -0,0 +1,74 @@
+use ethers::{
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use serde_derive::{Deserialize, Serialize};
+
+pub type AssetId = H256;
+
+// Balances of the chain coin, fees are always paid in it
+pub const NATIVE_ASSET: AssetId = H256::zero();
+
+// Longest token symbol accepted
+pub const MAX_SYMBOL_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenMetadata {
+    name: String,
+    symbol: String,
+}
+
+impl TokenMetadata {
+    pub fn new(name: String, symbol: String) -> TokenMetadata {
+        TokenMetadata { name, symbol }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn symbol(&self) -> &String {
+        &self.symbol
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.name.is_empty() && !self.symbol.is_empty() && self.symbol.len() <= MAX_SYMBOL_LENGTH
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenInfo {
+    issuer: Address,
+    metadata: TokenMetadata,
+    supply: U256,
+}
+
+impl TokenInfo {
+    pub fn new(issuer: Address, metadata: TokenMetadata, supply: U256) -> TokenInfo {
+        TokenInfo {
+            issuer,
+            metadata,
+            supply,
+        }
+    }
+
+    pub fn supply(&self) -> &U256 {
+        &self.supply
+    }
+
+    pub fn set_supply(&mut self, supply: U256) {
+        self.supply = supply;
+    }
+}
+
+/// Id of the token issued by `issuer` at the given nonce.
+pub fn derive_asset_id(issuer: &Address, nonce: &U256) -> AssetId {
+    let mut nonce_bytes = [0u8; 32];
+    nonce.to_big_endian(&mut nonce_bytes);
+
+    let mut preimage = b"zrush_asset:".to_vec();
+    preimage.extend_from_slice(issuer.as_bytes());
+    preimage.extend_from_slice(&nonce_bytes);
+
+    H256::from(keccak256(preimage))
+}
@@ -1,4 +1,6 @@
-use crate::{node::memory::NodeMemory, transaction::core::Transaction};
+use crate::{
+    node::memory::NodeMemory, signature::batch::VerifiedCache, transaction::core::Transaction,
+};
 pub const ZERO_HEX: &str = "0000000000000000000000000000000000000000000000000000000000000000";
 use chrono::Utc;
 use ethers::types::{Address, U256};
@@ -49,8 +51,15 @@ impl Block {
             transactions,
         }
     }
-    pub fn genesis_block(block_reward: U256, receiver: Address, timestamp: u64) -> Block {
+    pub fn genesis_block(
+        block_reward: U256,
+        receiver: Address,
+        timestamp: u64,
+        team_allocations: Vec<Transaction>,
+    ) -> Block {
         let creation_timestamp = Utc::now().timestamp_millis() as u64;
+        let mut transactions = vec![Transaction::genesis_tx(block_reward, receiver, timestamp)];
+        transactions.extend(team_allocations);
         Block {
             header: BlockHeader {
                 hash: String::from(ZERO_HEX),
@@ -62,15 +71,15 @@ impl Block {
             },
             height: U256::from(0),
             reward: block_reward,
-            transactions: vec![Transaction::genesis_tx(block_reward, receiver, timestamp)],
+            transactions,
         }
     }
 
-    pub fn validate(&self, mem: &NodeMemory) -> bool {
-        let difficulty_ok = *self.difficulty() == mem.block_difficulty();
-        let height_ok = *self.difficulty() == mem.block_height();
-        let reward_ok = *self.reward() == mem.block_reward();
-        true
+    /// `mem` is the confirmed state the block builds on, `verified` holds the
+    /// transactions whose signatures were already checked in the mempool.
+    pub fn validate(&self, mem: &NodeMemory, verified: &VerifiedCache) -> bool {
+        let height_ok = *self.height() == mem.block_height() + 1;
+        height_ok && mem.verify_block_signatures(self, verified).is_ok()
     }
 
     pub fn merkle_tx(txs: &Vec<Transaction>) -> String {
@@ -1,13 +1,51 @@
-use clap::{arg, command, Command};
+use clap::{arg, command, ArgAction, ArgMatches, Command};
 use crate::{
+    client::{
+        core::{fetch_decimals, send, ClientError, DEFAULT_NODE_URL},
+        history::{
+            wallet_balances, wallet_history, write_history_csv, HistorySource, HISTORY_CSV_HEADER,
+        },
+        offline::{
+            broadcast_offline_transaction, create_offline_transfer, sign_offline_transaction,
+            OfflineTransaction,
+        },
+        partial::{
+            combine_partial_transactions, create_partial_transfer, finalize_partial_transaction,
+            sign_partial_transaction,
+        },
+    },
     node::{
-        core::{create_new_blockchain, run_node, sync_node},
+        core::{create_new_blockchain, read_chain_config, run_node, sync_node},
         memory::NodeMemory,
     },
-    utils::files::read_from_file,
-    wallet::core::create_wallet,
+    signature::verification::KeyType,
+    utils::{
+        files::read_from_file,
+        input::{read_password_file, Input, InputError},
+        units::{format_amount, parse_amount as parse_units},
+    },
+    wallet::{
+        address_book::{add_contact, read_address_book, remove_contact, resolve_address},
+        core::{
+            add_watch_only_wallet, change_password, create_hd_wallet, create_wallet,
+            derive_wallet_account, export_wallet, import_wallet, list_wallets, wallet_address,
+            wallet_addresses, WalletStoreError,
+        },
+    },
 };
-use std::{process as runtime, sync::Mutex};
+use ethers::types::{Address, U256};
+use std::{env, process as runtime, sync::Mutex};
+
+// Answers read from flags of the same name
+const ANSWER_FLAGS: [&str; 7] = [
+    "name",
+    "decimals",
+    "chain-id",
+    "block-time",
+    "reward",
+    "halving-months",
+    "wallet-name",
+];
 
 pub async fn run_cli() {
     let matches = command!()
@@ -17,12 +55,47 @@ pub async fn run_cli() {
         .propagate_version(true)
         .subcommand_required(true)
         .arg_required_else_help(true)
+        .arg(
+            arg!(-y --yes "Accept confirmations and fail instead of prompting for missing values")
+                .global(true),
+        )
+        .arg(
+            arg!(--"password-file" <PATH> "Read the wallet password from the first line of a file")
+                .env("ZRUSH_PASSWORD_FILE")
+                .global(true),
+        )
+        .arg(
+            arg!(--decimals <DECIMALS> "Decimals of the chain's coin, asked from the node or read from data/chain_config.json when not given")
+                .env("ZRUSH_DECIMALS")
+                .global(true),
+        )
         .subcommand(
             Command::new("node")
                 .about("Run a node")
                 .arg(arg!(-p --port <PORT> "Sets the port to run on").required(false)),
         )
-        .subcommand(Command::new("createblockchain").about("Create a new blockchain"))
+        .subcommand(
+            Command::new("createblockchain")
+                .about("Create a new blockchain")
+                .arg(arg!(--name <NAME> "Chain name").env("ZRUSH_CHAIN_NAME"))
+                .arg(arg!(--"chain-id" <ID> "Chain id").env("ZRUSH_CHAIN_ID"))
+                .arg(arg!(--"block-time" <SECONDS> "Seconds between blocks").env("ZRUSH_BLOCK_TIME"))
+                .arg(arg!(--reward <AMOUNT> "Initial block reward").env("ZRUSH_INITIAL_REWARD"))
+                .arg(
+                    arg!(--"halving-months" <MONTHS> "Months between halvings")
+                        .env("ZRUSH_HALVING_MONTHS"),
+                )
+                .arg(
+                    arg!(--allocation <ALLOCATION> "Vested team allocation as \"<address> <amount> <cliff months> <vesting months>\", repeatable")
+                        .action(ArgAction::Append)
+                        .env("ZRUSH_TEAM_ALLOCATIONS")
+                        .value_delimiter(';'),
+                )
+                .arg(
+                    arg!(--"wallet-name" <NAME> "Name of the wallet receiving the genesis reward")
+                        .env("ZRUSH_WALLET_NAME"),
+                ),
+        )
         .subcommand(
             Command::new("sync")
                 .about("Sync to a new blockchain")
@@ -31,28 +104,225 @@ pub async fn run_cli() {
                 )
                 .arg(arg!(-p --port <PORT> "Sets the boot node to sync from").required(false)),
         )
-        .subcommand(Command::new("createwallet").about("Generates a new private key from an arbitrary password, cyphers it and stores it locally"))
+        .subcommand(
+            Command::new("createwallet")
+                .about("Generates a new private key from an arbitrary password, cyphers it and stores it locally")
+                .arg(
+                    arg!(-s --scheme <SCHEME> "Key type of the wallet")
+                        .value_parser(["secp256k1", "ed25519"])
+                        .default_value("secp256k1"),
+                )
+                .arg(
+                    arg!(-n --name <NAME> "Wallet name")
+                        .id("wallet-name")
+                        .alias("wallet-name")
+                        .env("ZRUSH_WALLET_NAME"),
+                )
+                .arg(arg!(-m --mnemonic "Generate or restore a BIP-39 mnemonic and derive accounts from it"))
+                .arg(
+                    arg!(-w --words <COUNT> "Number of words of a new mnemonic")
+                        .value_parser(["12", "24"])
+                        .default_value("12"),
+                ),
+        )
+        .subcommand(
+            Command::new("send")
+                .about("Sign a transfer with a wallet and submit it to a node")
+                .arg(arg!(-f --from <WALLET> "Wallet paying the transfer").required(true))
+                .arg(
+                    arg!(-i --index <INDEX> "Account of an HD wallet")
+                        .value_parser(clap::value_parser!(u32))
+                        .default_value("0"),
+                )
+                .arg(arg!(-t --to <ADDRESS> "Receiver address or address book label").required(true))
+                .arg(arg!(-a --amount <AMOUNT> "Amount to send").required(true))
+                .arg(arg!(--fee <FEE> "Fee paid to include the transaction").required(true))
+                .arg(arg!(-n --node <URL> "Node to submit to").default_value(DEFAULT_NODE_URL)),
+        )
+        .subcommand(
+            Command::new("tx")
+                .about("Create, sign and broadcast transaction files for offline signing")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Write an unsigned transfer, with the nonce fetched from a node")
+                        .arg(arg!(-f --from <ADDRESS> "Sender address, or the name of a watch-only wallet").required(true))
+                        .arg(arg!(-t --to <ADDRESS> "Receiver address or address book label").required(true))
+                        .arg(arg!(-a --amount <AMOUNT> "Amount to send").required(true))
+                        .arg(arg!(--fee <FEE> "Fee paid to include the transaction").required(true))
+                        .arg(arg!(-n --node <URL> "Node to fetch the nonce from").default_value(DEFAULT_NODE_URL))
+                        .arg(arg!(-o --out <FILE> "Transaction file to write").required(true)),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the sender, receivers, fee and signing message of a transaction file")
+                        .arg(arg!(<FILE> "Transaction file")),
+                )
+                .subcommand(
+                    Command::new("sign")
+                        .about("Sign a transaction file with a wallet, without network access")
+                        .arg(arg!(<FILE> "Transaction file, signed in place"))
+                        .arg(arg!(-w --wallet <WALLET> "Wallet of the sender").required(true))
+                        .arg(
+                            arg!(-i --index <INDEX> "Account of an HD wallet")
+                                .value_parser(clap::value_parser!(u32))
+                                .default_value("0"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("broadcast")
+                        .about("Submit a signed transaction file to a node")
+                        .arg(arg!(<FILE> "Signed transaction file"))
+                        .arg(arg!(-n --node <URL> "Node to submit to").default_value(DEFAULT_NODE_URL)),
+                ),
+        )
+        .subcommand(
+            Command::new("multisig")
+                .about("Gather the signatures of a multisig spend across machines")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Write a transfer out of a multisig account with no signatures yet")
+                        .arg(arg!(-f --from <ADDRESS> "Multisig account address").required(true))
+                        .arg(arg!(-t --to <ADDRESS> "Receiver address or address book label").required(true))
+                        .arg(arg!(-a --amount <AMOUNT> "Amount to send").required(true))
+                        .arg(arg!(--fee <FEE> "Fee paid to include the transaction").required(true))
+                        .arg(arg!(-n --node <URL> "Node to fetch the account and nonce from").default_value(DEFAULT_NODE_URL))
+                        .arg(arg!(-o --out <FILE> "Container file to write").required(true)),
+                )
+                .subcommand(
+                    Command::new("sign")
+                        .about("Add a cosigner's signature to a container")
+                        .arg(arg!(<FILE> "Container file, signed in place"))
+                        .arg(arg!(-w --wallet <WALLET> "Wallet of the cosigner").required(true))
+                        .arg(
+                            arg!(-i --index <INDEX> "Account of an HD wallet")
+                                .value_parser(clap::value_parser!(u32))
+                                .default_value("0"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("combine")
+                        .about("Merge the signatures of containers for the same transaction")
+                        .arg(arg!(<FILES> ... "Container files"))
+                        .arg(arg!(-o --out <FILE> "Combined container to write").required(true)),
+                )
+                .subcommand(
+                    Command::new("finalize")
+                        .about("Write the signed transaction once the threshold is met")
+                        .arg(arg!(<FILE> "Container file"))
+                        .arg(arg!(-o --out <FILE> "Transaction file for `tx broadcast`").required(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("addressbook")
+                .about("Label counterparty addresses so commands accept the label instead")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("list").about("List labels with their addresses"))
+                .subcommand(
+                    Command::new("add")
+                        .about("Label an address")
+                        .arg(arg!(<LABEL> "Label"))
+                        .arg(arg!(<ADDRESS> "Address")),
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove a label")
+                        .arg(arg!(<LABEL> "Label")),
+                ),
+        )
+        .subcommand(
+            Command::new("wallet")
+                .about("Manage the wallets stored in .keys")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("list").about("List wallets with their addresses"))
+                .subcommand(
+                    Command::new("import")
+                        .about("Import a wallet from a keystore file, or from a private key read from stdin")
+                        .arg(arg!(<NAME> "Name of the new wallet"))
+                        .arg(arg!(-k --keystore <PATH> "Keystore JSON file to import").required(false))
+                        .arg(
+                            arg!(-s --scheme <SCHEME> "Key type of the wallet")
+                                .value_parser(["secp256k1", "ed25519"])
+                                .default_value("secp256k1"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Print the encrypted keystore of a wallet")
+                        .arg(arg!(<NAME> "Wallet to export"))
+                        .arg(arg!(--"private-key" "Print the decrypted private key instead")),
+                )
+                .subcommand(
+                    Command::new("address")
+                        .about("Print the address of a wallet")
+                        .arg(arg!(<NAME> "Wallet name")),
+                )
+                .subcommand(
+                    Command::new("derive")
+                        .about("Derive another account of an HD wallet")
+                        .arg(arg!(<NAME> "Wallet name"))
+                        .arg(
+                            arg!(-i --index <INDEX> "Account index along the BIP-44 path")
+                                .value_parser(clap::value_parser!(u32)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("watch")
+                        .about("Track an address without its private key")
+                        .arg(arg!(<NAME> "Name of the watch-only wallet"))
+                        .arg(arg!(<ADDRESS> "Address to track")),
+                )
+                .subcommand(
+                    Command::new("history")
+                        .about("List the transactions touching a wallet's addresses")
+                        .arg(arg!(<NAME> "Wallet name"))
+                        .arg(arg!(-n --node <URL> "Node to query").default_value(DEFAULT_NODE_URL))
+                        .arg(arg!(--local "Scan the chain in data/storage instead of asking a node"))
+                        .arg(arg!(--csv <FILE> "Write the history as CSV").required(false)),
+                )
+                .subcommand(
+                    Command::new("balance")
+                        .about("Show the balance of each of a wallet's addresses")
+                        .arg(arg!(<NAME> "Wallet name"))
+                        .arg(arg!(-n --node <URL> "Node to query").default_value(DEFAULT_NODE_URL))
+                        .arg(arg!(--local "Replay the chain in data/storage instead of asking a node")),
+                )
+                .subcommand(
+                    Command::new("change-password")
+                        .about("Re-encrypt a wallet under a new password")
+                        .arg(arg!(<NAME> "Wallet name"))
+                        .arg(
+                            arg!(--"new-password-file" <PATH> "Read the new password from the first line of a file")
+                                .env("ZRUSH_NEW_PASSWORD_FILE"),
+                        ),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
         Some(("node", _sub_matches)) => {
             let chain = read_from_file("data/storage", "chain_data.json").unwrap();
             let node_memory = NodeMemory::cache(&chain).unwrap_or_else(|err| {
-                eprintln!("{:?}", err);
+                eprintln!("{}", err);
                 runtime::exit(1);
             });
             if let Some(port) = _sub_matches.get_one::<String>("port") {
                 run_node(port.to_string(), Mutex::new(node_memory))
                     .await
                     .unwrap_or_else(|err| {
-                        eprintln!("{:?}", err);
+                        eprintln!("{}", err);
                         runtime::exit(1);
                     });
             } else {
                 run_node(String::from("50051"), Mutex::new(node_memory))
                     .await
                     .unwrap_or_else(|err| {
-                        eprintln!("{:?}", err);
+                        eprintln!("{}", err);
                         runtime::exit(1);
                     });
             }
@@ -62,51 +332,454 @@ pub async fn run_cli() {
                 sync_node(node_addr.to_string())
                     .await
                     .unwrap_or_else(|err| {
-                        eprintln!("{:?}", err);
+                        eprintln!("{}", err);
                         runtime::exit(1);
                     });
                 let chain = read_from_file("data/storage", "chain_data.json").unwrap();
                 let node_memory = NodeMemory::cache(&chain).unwrap_or_else(|err| {
-                    eprintln!("{:?}", err);
+                    eprintln!("{}", err);
                     runtime::exit(1);
                 });
                 if let Some(port) = _sub_matches.get_one::<String>("port") {
                     run_node(port.to_string(), Mutex::new(node_memory))
                         .await
                         .unwrap_or_else(|err| {
-                            eprintln!("{:?}", err);
+                            eprintln!("{}", err);
                             runtime::exit(1);
                         });
                 } else {
                     run_node(String::from("50051"), Mutex::new(node_memory))
                         .await
                         .unwrap_or_else(|err| {
-                            eprintln!("{:?}", err);
+                            eprintln!("{}", err);
                             runtime::exit(1);
                         });
                 }
             }
         }
         Some(("createblockchain", _sub_matches)) => {
-            create_new_blockchain().unwrap_or_else(|err| {
-                eprintln!("{:?}", err);
+            create_new_blockchain(&command_input(_sub_matches)).unwrap_or_else(|err| {
+                eprintln!("{}", err);
                 runtime::exit(1);
             });
             let chain = read_from_file("data/storage", "chain_data.json").unwrap();
             let node_memory = NodeMemory::cache(&chain).unwrap_or_else(|err| {
-                eprintln!("{:?}", err);
+                eprintln!("{}", err);
                 runtime::exit(1);
             });
             run_node(String::from("50051"), Mutex::new(node_memory))
                 .await
                 .unwrap_or_else(|err| {
-                    eprintln!("{:?}", err);
+                    eprintln!("{}", err);
                     runtime::exit(1);
                 });
         }
         Some(("createwallet", _sub_matches)) => {
-            let _ = create_wallet();
+            let key_type = parse_key_type(_sub_matches);
+            let input = command_input(_sub_matches);
+            let res = if _sub_matches.get_flag("mnemonic") {
+                match key_type {
+                    KeyType::Secp256k1 => {
+                        let words = _sub_matches.get_one::<String>("words").unwrap();
+                        create_hd_wallet(words.parse().unwrap(), &input)
+                    }
+                    KeyType::Ed25519 => Err(WalletStoreError::InvalidInput(String::from(
+                        "HD wallets only derive secp256k1 keys",
+                    ))),
+                }
+            } else {
+                create_wallet(key_type, &input)
+            };
+            res.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                runtime::exit(1);
+            });
+        }
+        Some(("wallet", _sub_matches)) => run_wallet_command(_sub_matches).await,
+        Some(("addressbook", _sub_matches)) => run_address_book_command(_sub_matches),
+        Some(("tx", _sub_matches)) => run_tx_command(_sub_matches).await,
+        Some(("multisig", _sub_matches)) => run_multisig_command(_sub_matches).await,
+        Some(("send", _sub_matches)) => {
+            let arg = |id: &str| _sub_matches.get_one::<String>(id).unwrap();
+            let res = match (
+                parse_address(arg("to")),
+                transfer_amounts(_sub_matches).await,
+            ) {
+                (Ok(to), Ok((amount, fee))) => {
+                    let index = *_sub_matches.get_one::<u32>("index").unwrap();
+                    let input = command_input(_sub_matches);
+                    send(arg("from"), index, to, amount, fee, arg("node"), &input).await
+                }
+                (Err(err), _) | (_, Err(err)) => Err(err),
+            };
+            match res {
+                Ok(tx_id) => println!("{}", tx_id),
+                Err(err) => {
+                    eprintln!("{}", err);
+                    runtime::exit(1);
+                }
+            }
+        }
+        _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
+    }
+}
+
+fn parse_key_type(matches: &ArgMatches) -> KeyType {
+    match matches.get_one::<String>("scheme").map(String::as_str) {
+        Some("ed25519") => KeyType::Ed25519,
+        _ => KeyType::Secp256k1,
+    }
+}
+
+async fn run_wallet_command(matches: &ArgMatches) {
+    let name = |sub_matches: &ArgMatches| sub_matches.get_one::<String>("NAME").unwrap().clone();
+    let res = match matches.subcommand() {
+        Some(("list", _)) => list_wallets().map(|wallets| {
+            for (name, entry) in wallets {
+                match entry {
+                    Some(entry) if entry.is_hd() => {
+                        println!("{}\t{:?}\tHD", name, entry.address());
+                        for account in entry.accounts() {
+                            println!("  #{}\t{:?}", account.index(), account.address());
+                        }
+                    }
+                    Some(entry) if entry.is_watch_only() => {
+                        println!("{}\t{:?}\twatch-only", name, entry.address())
+                    }
+                    Some(entry) => {
+                        println!("{}\t{:?}\t{:?}", name, entry.address(), entry.key_type())
+                    }
+                    None => println!("{}\t(locked, run `wallet address {}`)", name, name),
+                }
+            }
+        }),
+        Some(("import", sub_matches)) => import_wallet(
+            &name(sub_matches),
+            sub_matches
+                .get_one::<String>("keystore")
+                .map(String::as_str),
+            parse_key_type(sub_matches),
+            &command_input(sub_matches),
+        )
+        .map(|addr| println!("Imported wallet {:?}", addr)),
+        Some(("export", sub_matches)) => export_wallet(
+            &name(sub_matches),
+            sub_matches.get_flag("private-key"),
+            &command_input(sub_matches),
+        )
+        .map(|exported| println!("{}", exported)),
+        Some(("address", sub_matches)) => {
+            wallet_address(&name(sub_matches), &command_input(sub_matches))
+                .map(|addr| println!("{:?}", addr))
+        }
+        Some(("derive", sub_matches)) => {
+            let index = *sub_matches.get_one::<u32>("index").unwrap_or(&0);
+            derive_wallet_account(&name(sub_matches), index, &command_input(sub_matches))
+                .map(|addr| println!("#{}\t{:?}", index, addr))
+        }
+        Some(("change-password", sub_matches)) => {
+            change_password(&name(sub_matches), &command_input(sub_matches))
+                .map(|_| println!("Password changed"))
         }
+        Some(("watch", sub_matches)) => {
+            let address = sub_matches.get_one::<String>("ADDRESS").unwrap();
+            match address.parse::<Address>() {
+                Ok(address) => add_watch_only_wallet(&name(sub_matches), address)
+                    .map(|_| println!("Watching {:?}", address)),
+                Err(_) => Err(WalletStoreError::InvalidInput(format!(
+                    "Invalid address: {address}"
+                ))),
+            }
+        }
+        Some((command @ ("history" | "balance"), sub_matches)) => {
+            let res = match wallet_addresses(&name(sub_matches), &command_input(sub_matches)) {
+                Ok(addresses) if command == "history" => {
+                    print_wallet_history(sub_matches, &addresses).await
+                }
+                Ok(addresses) => print_wallet_balance(sub_matches, &addresses).await,
+                Err(err) => Err(ClientError::WalletError(err)),
+            };
+            res.unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                runtime::exit(1);
+            });
+            Ok(())
+        }
+        _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
+    };
+    res.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        runtime::exit(1);
+    });
+}
+
+fn run_address_book_command(matches: &ArgMatches) {
+    let label = |sub_matches: &ArgMatches| sub_matches.get_one::<String>("LABEL").unwrap().clone();
+    let res = match matches.subcommand() {
+        Some(("list", _)) => read_address_book().map(|contacts| {
+            for contact in contacts {
+                println!("{}\t{:?}", contact.label(), contact.address());
+            }
+        }),
+        Some(("add", sub_matches)) => {
+            let address = sub_matches.get_one::<String>("ADDRESS").unwrap();
+            match address.parse::<Address>() {
+                Ok(address) => add_contact(&label(sub_matches), address)
+                    .map(|_| println!("Saved {} as {:?}", label(sub_matches), address)),
+                Err(_) => Err(WalletStoreError::InvalidInput(format!(
+                    "Invalid address: {address}"
+                ))),
+            }
+        }
+        Some(("remove", sub_matches)) => {
+            remove_contact(&label(sub_matches)).map(|_| println!("Removed {}", label(sub_matches)))
+        }
+        _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
+    };
+    res.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        runtime::exit(1);
+    });
+}
+
+fn history_source(matches: &ArgMatches) -> HistorySource<'_> {
+    match matches.get_flag("local") {
+        true => HistorySource::LocalChain,
+        false => HistorySource::Node(matches.get_one::<String>("node").unwrap()),
+    }
+}
+
+async fn print_wallet_history(
+    matches: &ArgMatches,
+    addresses: &[Address],
+) -> Result<(), ClientError> {
+    let decimals = amount_decimals(matches).await?;
+    let entries = wallet_history(addresses, history_source(matches)).await?;
+    if let Some(path) = matches.get_one::<String>("csv") {
+        write_history_csv(&entries, path, decimals)?;
+        println!("{} transactions saved to {}", entries.len(), path);
+        return Ok(());
+    }
+    println!("{}", HISTORY_CSV_HEADER.replace(',', "\t"));
+    for entry in entries.iter() {
+        println!("{}", entry.to_row(decimals).join("\t"));
+    }
+    Ok(())
+}
+
+async fn print_wallet_balance(
+    matches: &ArgMatches,
+    addresses: &[Address],
+) -> Result<(), ClientError> {
+    let decimals = amount_decimals(matches).await?;
+    let balances = wallet_balances(addresses, history_source(matches)).await?;
+    let mut total = U256::from(0);
+    for balance in balances.iter() {
+        println!(
+            "{:?}\t{}\t(spendable {})",
+            balance.address(),
+            format_amount(balance.balance(), decimals),
+            format_amount(balance.spendable(), decimals)
+        );
+        total = total.saturating_add(*balance.balance());
+    }
+    println!("Total\t{}", format_amount(&total, decimals));
+    Ok(())
+}
+
+async fn run_tx_command(matches: &ArgMatches) {
+    let res = match matches.subcommand() {
+        Some(("create", sub_matches)) => {
+            let arg = |id: &str| sub_matches.get_one::<String>(id).unwrap();
+            match (
+                parse_address(arg("from")),
+                parse_address(arg("to")),
+                transfer_amounts(sub_matches).await,
+            ) {
+                (Ok(from), Ok(to), Ok((amount, fee))) => {
+                    create_offline_transfer(from, to, amount, fee, arg("node"), arg("out"))
+                        .await
+                        .map(|_| println!("Unsigned transaction saved to {}", arg("out")))
+                }
+                (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => Err(err),
+            }
+        }
+        Some(("show", sub_matches)) => {
+            show_offline_transaction(sub_matches.get_one::<String>("FILE").unwrap(), sub_matches)
+                .await
+        }
+        Some(("sign", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("FILE").unwrap();
+            show_offline_transaction(path, sub_matches)
+                .await
+                .and_then(|_| {
+                    sign_offline_transaction(
+                        path,
+                        sub_matches.get_one::<String>("wallet").unwrap(),
+                        *sub_matches.get_one::<u32>("index").unwrap(),
+                        &command_input(sub_matches),
+                    )
+                })
+                .map(|_| println!("Signed transaction saved to {}", path))
+        }
+        Some(("broadcast", sub_matches)) => broadcast_offline_transaction(
+            sub_matches.get_one::<String>("FILE").unwrap(),
+            sub_matches.get_one::<String>("node").unwrap(),
+        )
+        .await
+        .map(|tx_id| println!("{}", tx_id)),
         _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
+    };
+    res.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        runtime::exit(1);
+    });
+}
+
+async fn show_offline_transaction(path: &str, matches: &ArgMatches) -> Result<(), ClientError> {
+    let decimals = amount_decimals(matches).await?;
+    OfflineTransaction::read(path)?
+        .summary(decimals)
+        .iter()
+        .for_each(|line| println!("{}", line));
+    Ok(())
+}
+
+async fn run_multisig_command(matches: &ArgMatches) {
+    let res = match matches.subcommand() {
+        Some(("create", sub_matches)) => {
+            let arg = |id: &str| sub_matches.get_one::<String>(id).unwrap();
+            match (
+                parse_address(arg("from")),
+                parse_address(arg("to")),
+                transfer_amounts(sub_matches).await,
+            ) {
+                (Ok(from), Ok(to), Ok((amount, fee))) => {
+                    create_partial_transfer(from, to, amount, fee, arg("node"), arg("out"))
+                        .await
+                        .map(|_| println!("Unsigned container saved to {}", arg("out")))
+                }
+                (Err(err), _, _) | (_, Err(err), _) | (_, _, Err(err)) => Err(err),
+            }
+        }
+        Some(("sign", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("FILE").unwrap();
+            sign_partial_transaction(
+                path,
+                sub_matches.get_one::<String>("wallet").unwrap(),
+                *sub_matches.get_one::<u32>("index").unwrap(),
+                &command_input(sub_matches),
+            )
+            .map(|_| println!("Signature added to {}", path))
+        }
+        Some(("combine", sub_matches)) => {
+            let paths: Vec<String> = sub_matches
+                .get_many::<String>("FILES")
+                .unwrap()
+                .cloned()
+                .collect();
+            let out = sub_matches.get_one::<String>("out").unwrap();
+            combine_partial_transactions(&paths, out)
+                .map(|_| println!("Combined container saved to {}", out))
+        }
+        Some(("finalize", sub_matches)) => {
+            let out = sub_matches.get_one::<String>("out").unwrap();
+            finalize_partial_transaction(sub_matches.get_one::<String>("FILE").unwrap(), out)
+                .map(|_| println!("Signed transaction saved to {}", out))
+        }
+        _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
+    };
+    res.unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        runtime::exit(1);
+    });
+}
+
+// Answers given as flags, environment variables or password files, so commands can
+// run without anyone at the terminal
+fn command_input(matches: &ArgMatches) -> Input {
+    read_command_input(matches).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        runtime::exit(1);
+    })
+}
+
+fn read_command_input(matches: &ArgMatches) -> Result<Input, InputError> {
+    let mut input = Input::new(matches.get_flag("yes"));
+    for id in ANSWER_FLAGS {
+        if let Some(answer) = matches.try_get_one::<String>(id).ok().flatten() {
+            input = input.with_answer(id, answer.clone());
+        }
+    }
+    if let Some(allocations) = matches.try_get_many::<String>("allocation").ok().flatten() {
+        input = input.with_list("allocation", allocations.cloned().collect());
+    }
+
+    // Secrets are never flags, they would show up in the process list
+    let passwords = [
+        ("password", "password-file", "ZRUSH_PASSWORD"),
+        ("new-password", "new-password-file", "ZRUSH_NEW_PASSWORD"),
+    ];
+    for (id, file_flag, var) in passwords {
+        let password = match matches.try_get_one::<String>(file_flag).ok().flatten() {
+            Some(path) => Some(read_password_file(path)?),
+            None => env::var(var).ok(),
+        };
+        if let Some(password) = password {
+            input = input.with_answer(id, password);
+        }
+    }
+    for (id, var) in [
+        ("private-key", "ZRUSH_PRIVATE_KEY"),
+        ("mnemonic", "ZRUSH_MNEMONIC"),
+    ] {
+        if let Ok(secret) = env::var(var) {
+            input = input.with_answer(id, secret);
+        }
+    }
+    Ok(input)
+}
+
+// Hex addresses, address book labels and wallet names are all accepted
+fn parse_address(input: &str) -> Result<Address, ClientError> {
+    resolve_address(input).map_err(ClientError::WalletError)
+}
+
+// Whole coins unless suffixed with a unit, e.g. `1.5`, `250 milli` or `42base`
+fn parse_amount(matches: &ArgMatches, id: &str, decimals: u8) -> Result<U256, ClientError> {
+    let input = matches.get_one::<String>(id).unwrap();
+    parse_units(input, decimals)
+        .map_err(|err| ClientError::InvalidInput(format!("Invalid amount {input}: {err}")))
+}
+
+// `--amount` and `--fee` of a transfer
+async fn transfer_amounts(matches: &ArgMatches) -> Result<(U256, U256), ClientError> {
+    let decimals = amount_decimals(matches).await?;
+    Ok((
+        parse_amount(matches, "amount", decimals)?,
+        parse_amount(matches, "fee", decimals)?,
+    ))
+}
+
+// `--decimals` first, then the node the command talks to, then the local chain config.
+// A guess could scale amounts by orders of magnitude, so there is no default
+async fn amount_decimals(matches: &ArgMatches) -> Result<u8, ClientError> {
+    if let Some(decimals) = matches.try_get_one::<String>("decimals").ok().flatten() {
+        return decimals
+            .parse::<u8>()
+            .map_err(|_| ClientError::InvalidInput(format!("Invalid decimals: {decimals}")));
+    }
+    let local = matches.try_get_one::<bool>("local").ok().flatten() == Some(&true);
+    if let Some(node_url) = matches.try_get_one::<String>("node").ok().flatten() {
+        if !local {
+            if let Some(decimals) = fetch_decimals(node_url).await? {
+                return Ok(decimals);
+            }
+        }
     }
+    read_chain_config()
+        .map(|config| config.decimals())
+        .ok_or(ClientError::InvalidInput(String::from(
+            "Unknown coin decimals, pass them with --decimals",
+        )))
 }
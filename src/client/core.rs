@@ -0,0 +1,140 @@
+use std::fmt;
+
+use ethers::types::{Address, U256};
+use tonic::Request;
+
+use crate::{
+    node::{
+        core::{parse_grpc_multisig_setup, transaction_to_grpc},
+        node_proto::node_proto,
+    },
+    signature::multisig::MultisigAccount,
+    transaction::core::Transaction,
+    utils::{
+        ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE},
+        input::Input,
+    },
+    wallet::core::{ask_password, unlock_account, WalletStoreError},
+};
+use node_proto::{node_client::NodeClient, ChainHeadRequest, MultisigRequest, NonceRequest};
+
+pub const DEFAULT_NODE_URL: &str = "http://127.0.0.1:50051";
+
+#[derive(Debug)]
+pub enum ClientError {
+    InvalidInput(String),
+    WalletError(WalletStoreError),
+    NetworkError(String),
+    RequestError(String),
+    IoError(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::InvalidInput(msg) | ClientError::IoError(msg) => write!(f, "{msg}"),
+            ClientError::WalletError(err) => write!(f, "{err}"),
+            ClientError::NetworkError(msg) => write!(f, "Could not reach node: {msg}"),
+            ClientError::RequestError(msg) => write!(f, "Request failed: {msg}"),
+        }
+    }
+}
+
+pub async fn connect(node_url: &str) -> Result<NodeClient<tonic::transport::Channel>, ClientError> {
+    NodeClient::connect(node_url.to_string())
+        .await
+        .map_err(|err| ClientError::NetworkError(format!("{node_url}: {err}")))
+}
+
+/// Decimals of the node's coin, `None` when the node has no chain config.
+pub async fn fetch_decimals(node_url: &str) -> Result<Option<u8>, ClientError> {
+    let mut client = connect(node_url).await?;
+    let res = client
+        .get_chain_head(Request::new(ChainHeadRequest {}))
+        .await
+        .map_err(|status| ClientError::RequestError(status.message().to_string()))?
+        .into_inner();
+    res.decimals
+        .map(|decimals| {
+            u8::try_from(decimals)
+                .map_err(|_| ClientError::RequestError(String::from("Invalid decimals")))
+        })
+        .transpose()
+}
+
+/// Next nonce of the address, counting the transactions pending in the node's mempool.
+pub async fn fetch_nonce(node_url: &str, address: &Address) -> Result<U256, ClientError> {
+    let mut client = connect(node_url).await?;
+    let res = client
+        .get_nonce(Request::new(NonceRequest {
+            address: address.as_bytes().to_vec(),
+        }))
+        .await
+        .map_err(|status| ClientError::RequestError(status.message().to_string()))?;
+    let nonce: [u8; 32] = res
+        .into_inner()
+        .nonce
+        .try_into()
+        .map_err(|_| ClientError::RequestError(String::from("Invalid nonce")))?;
+    Ok(U256::from(nonce))
+}
+
+/// Signer set and threshold of the multisig account registered at `address`.
+pub async fn fetch_multisig(
+    node_url: &str,
+    address: &Address,
+) -> Result<MultisigAccount, ClientError> {
+    let mut client = connect(node_url).await?;
+    let res = client
+        .get_multisig(Request::new(MultisigRequest {
+            address: address.as_bytes().to_vec(),
+        }))
+        .await
+        .map_err(|status| ClientError::RequestError(status.message().to_string()))?;
+    let setup = res
+        .into_inner()
+        .account
+        .ok_or(ClientError::RequestError(String::from(
+            "Missing multisig account",
+        )))?;
+    parse_grpc_multisig_setup(setup).map_err(|err| ClientError::RequestError(err.to_string()))
+}
+
+/// Submits a signed transaction and returns its id.
+pub async fn broadcast_transaction(
+    node_url: &str,
+    tx: &Transaction,
+) -> Result<String, ClientError> {
+    let mut client = connect(node_url).await?;
+    let res = client
+        .request_send_transaction(Request::new(transaction_to_grpc(tx)))
+        .await
+        .map_err(|status| ClientError::RequestError(status.message().to_string()))?;
+    Ok(res.into_inner().tx_id)
+}
+
+/// Plain transfer left unsigned. The node stamps the transaction when it receives it.
+pub fn unsigned_transfer(from: Address, to: Address, amount: U256, fee: U256) -> Transaction {
+    Transaction::new(from, to, amount, fee, EMPTY_SIGNATURE(), 0, ADDRESS_ZERO())
+}
+
+/// Signs a transfer with the wallet's account `index` and broadcasts it.
+pub async fn send(
+    from: &str,
+    index: u32,
+    to: Address,
+    amount: U256,
+    fee: U256,
+    node_url: &str,
+    input: &Input,
+) -> Result<String, ClientError> {
+    let password = ask_password(input).map_err(ClientError::WalletError)?;
+    let key = unlock_account(from, &password, index).map_err(ClientError::WalletError)?;
+    let sender = key.address();
+    let nonce = fetch_nonce(node_url, &sender).await?;
+
+    let tx = key
+        .sign_transaction(unsigned_transfer(sender, to, amount, fee), &nonce)
+        .map_err(ClientError::WalletError)?;
+    broadcast_transaction(node_url, &tx).await
+}
@@ -0,0 +1,427 @@
+use std::fs;
+
+use ethers::types::{Address, U256};
+use tonic::Request;
+
+use crate::{
+    asset::core::AssetId,
+    node::{
+        core::{parse_grpc_transaction_request, read_chain},
+        history::address_transactions,
+        memory::{Movement, NodeMemory},
+        node_proto::node_proto,
+    },
+    transaction::{
+        core::{Transaction, TransactionKind},
+        receipt::TransactionStatus,
+    },
+    utils::{ethers_empty_types::ADDRESS_ZERO, timestamp::current_timestamp, units::format_amount},
+};
+use node_proto::{
+    AddressHistoryRequest, BalanceRequest, TransactionStatus as GrpcTransactionStatus,
+};
+
+use super::core::{connect, ClientError};
+
+pub const HISTORY_CSV_HEADER: &str =
+    "tx_id,timestamp,kind,direction,counterparty,amount,fee,confirmations,memo";
+
+/// Where wallet activity is read from.
+pub enum HistorySource<'a> {
+    Node(&'a str),
+    // The chain stored in `data/storage` by a local node
+    LocalChain,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Incoming,
+    Outgoing,
+    // Between the wallet's own addresses, or with no counterparty at all
+    Internal,
+}
+
+impl Direction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Incoming => "in",
+            Direction::Outgoing => "out",
+            Direction::Internal => "self",
+        }
+    }
+}
+
+/// A transaction as seen from a wallet owning `addresses`.
+#[derive(Debug, Clone)]
+pub struct HistoryEntry {
+    tx_id: String,
+    timestamp: u64,
+    kind: &'static str,
+    direction: Direction,
+    counterparties: Vec<Address>,
+    // Native coin moved in or out of the wallet, or token units when `asset` is set
+    amount: U256,
+    asset: Option<AssetId>,
+    // Fee paid by the wallet, zero when someone else paid it
+    fee: U256,
+    // Zero while pending
+    confirmations: U256,
+    memo: String,
+}
+
+impl HistoryEntry {
+    /// `settlements` are the coins the node moved when executing the transaction, see
+    /// `NodeMemory::settlements`.
+    pub fn new(
+        tx_id: String,
+        tx: &Transaction,
+        settlements: &[Movement],
+        timestamp: u64,
+        status: &TransactionStatus,
+        head_height: &U256,
+        addresses: &[Address],
+    ) -> HistoryEntry {
+        let owned = |addr: &Address| addresses.contains(addr);
+        let sender = match tx.kind() {
+            TransactionKind::TransferFrom(owner) => *owner,
+            _ => *tx.from(),
+        };
+        let mut movements = native_movements(tx);
+        movements.extend_from_slice(settlements);
+        let moved = |filter: &dyn Fn(&Address, &Address) -> bool| {
+            movements
+                .iter()
+                .filter(|(from, to, _)| filter(from, to))
+                .fold(U256::from(0), |acc, (_, _, amount)| {
+                    acc.saturating_add(*amount)
+                })
+        };
+        let received = moved(&|from, to| !owned(from) && owned(to));
+        let sent = moved(&|from, to| owned(from) && !owned(to));
+
+        let mut counterparties: Vec<Address> = match owned(&sender) {
+            true => tx
+                .parties()
+                .into_iter()
+                .chain(
+                    settlements
+                        .iter()
+                        .flat_map(|(from, to, _)| [*from, *to])
+                        .filter(|addr| !addr.is_zero()),
+                )
+                .filter(|party| !owned(party))
+                .collect(),
+            false => vec![sender],
+        };
+        counterparties.sort();
+        counterparties.dedup();
+        // Funds coming back, such as a refunded HTLC, are incoming whoever sent the
+        // transaction
+        let direction = match (
+            owned(&sender) || owned(tx.fee_payer()),
+            counterparties.is_empty(),
+        ) {
+            _ if sent.is_zero() && !received.is_zero() => Direction::Incoming,
+            (true, true) => Direction::Internal,
+            (true, false) => Direction::Outgoing,
+            (false, _) => Direction::Incoming,
+        };
+
+        let (amount, asset) = match tx.kind() {
+            TransactionKind::TransferToken(asset_id) => (*tx.amount(), Some(*asset_id)),
+            _ => match direction {
+                Direction::Incoming => (received, None),
+                Direction::Outgoing => (sent, None),
+                Direction::Internal => (moved(&|_, _| true), None),
+            },
+        };
+        let fee = match owned(tx.fee_payer()) {
+            true => *tx.fee_amount(),
+            false => U256::from(0),
+        };
+        let confirmations = match status {
+            TransactionStatus::Included { height, .. } => head_height
+                .saturating_sub(*height)
+                .saturating_add(U256::from(1)),
+            _ => U256::from(0),
+        };
+        // Memos are usually text such as invoice ids, anything else is shown as hex
+        let memo = String::from_utf8(tx.data().clone())
+            .unwrap_or_else(|_| format!("0x{}", hex::encode(tx.data())));
+
+        HistoryEntry {
+            tx_id,
+            timestamp,
+            kind: tx.kind().name(),
+            direction,
+            counterparties,
+            amount,
+            asset,
+            fee,
+            confirmations,
+            memo,
+        }
+    }
+
+    /// Counterparties joined by spaces, `-` when there are none.
+    pub fn counterparty(&self) -> String {
+        match self.counterparties.is_empty() {
+            true => String::from("-"),
+            false => self
+                .counterparties
+                .iter()
+                .map(|addr| format!("{:?}", addr))
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
+
+    /// Amount in coins formatted with `decimals`, or token units followed by the asset id.
+    pub fn amount(&self, decimals: u8) -> String {
+        match self.asset {
+            Some(asset_id) => format!("{} {:?}", self.amount, asset_id),
+            None => format_amount(&self.amount, decimals),
+        }
+    }
+
+    /// Fields in `HISTORY_CSV_HEADER` order, native amounts formatted with `decimals`.
+    pub fn to_row(&self, decimals: u8) -> Vec<String> {
+        vec![
+            self.tx_id.clone(),
+            self.timestamp.to_string(),
+            self.kind.to_string(),
+            self.direction.name().to_string(),
+            self.counterparty(),
+            self.amount(decimals),
+            format_amount(&self.fee, decimals),
+            self.confirmations.to_string(),
+            self.memo.clone(),
+        ]
+    }
+}
+
+pub struct AddressBalance {
+    address: Address,
+    balance: U256,
+    // Part of the balance that isn't time locked
+    spendable: U256,
+}
+
+impl AddressBalance {
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn balance(&self) -> &U256 {
+        &self.balance
+    }
+
+    pub fn spendable(&self) -> &U256 {
+        &self.spendable
+    }
+}
+
+// Every native coin transfer listed in the transaction. HTLC funds are held by the
+// zero address until a settlement pays them out.
+fn native_movements(tx: &Transaction) -> Vec<Movement> {
+    let mut movements: Vec<Movement> = tx
+        .outputs()
+        .iter()
+        .map(|output| (*tx.from(), *output.to(), *output.amount()))
+        .collect();
+    match tx.kind() {
+        TransactionKind::HtlcLock { .. } => {
+            movements.push((*tx.from(), ADDRESS_ZERO(), *tx.amount()))
+        }
+        TransactionKind::TransferFrom(owner) => movements.push((*owner, *tx.to(), *tx.amount())),
+        _ => {}
+    }
+    movements
+}
+
+/// Every transaction involving `addresses`, oldest first.
+pub async fn wallet_history(
+    addresses: &[Address],
+    source: HistorySource<'_>,
+) -> Result<Vec<HistoryEntry>, ClientError> {
+    match source {
+        HistorySource::LocalChain => {
+            let chain = read_chain().map_err(|err| ClientError::IoError(err.to_string()))?;
+            let head_height = chain
+                .last()
+                .map(|block| *block.height())
+                .unwrap_or_default();
+            let mem = NodeMemory::replay(&chain)
+                .map_err(|err| ClientError::IoError(format!("{}", err)))?;
+            Ok(address_transactions(&chain, &[], &mem, addresses)
+                .iter()
+                .map(|(tx, status)| {
+                    let tx_id = tx.id();
+                    HistoryEntry::new(
+                        tx_id.clone(),
+                        tx,
+                        &mem.settlements(&tx_id),
+                        tx.timestamp(),
+                        status,
+                        &head_height,
+                        addresses,
+                    )
+                })
+                .collect())
+        }
+        HistorySource::Node(node_url) => {
+            let mut client = connect(node_url).await?;
+            let res = client
+                .get_address_history(Request::new(AddressHistoryRequest {
+                    addresses: addresses
+                        .iter()
+                        .map(|addr| addr.as_bytes().to_vec())
+                        .collect(),
+                }))
+                .await
+                .map_err(|status| ClientError::RequestError(status.message().to_string()))?
+                .into_inner();
+            let head_height = parse_u256_bytes(res.head_height)?;
+
+            let mut entries = vec![];
+            for found in res.transactions {
+                let invalid = || ClientError::RequestError(String::from("Invalid transaction"));
+                let tx = parse_grpc_transaction_request(found.transaction.ok_or_else(invalid)?)
+                    .map_err(|err| ClientError::RequestError(err.to_string()))?;
+                let receipt = found.receipt.ok_or_else(invalid)?;
+                let status = match receipt.status() {
+                    GrpcTransactionStatus::Included => TransactionStatus::Included {
+                        height: parse_u256_bytes(receipt.height)?,
+                        block_hash: receipt.block_hash,
+                        index: receipt.index,
+                    },
+                    _ => TransactionStatus::Pending,
+                };
+                let settlements = found
+                    .settlements
+                    .into_iter()
+                    .map(|settlement| {
+                        Ok((
+                            parse_address_bytes(settlement.from)?,
+                            parse_address_bytes(settlement.to)?,
+                            parse_u256_bytes(settlement.amount)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<Movement>, ClientError>>()?;
+                entries.push(HistoryEntry::new(
+                    found.tx_id,
+                    &tx,
+                    &settlements,
+                    found.timestamp,
+                    &status,
+                    &head_height,
+                    addresses,
+                ));
+            }
+            Ok(entries)
+        }
+    }
+}
+
+/// Native coin balance of each address.
+pub async fn wallet_balances(
+    addresses: &[Address],
+    source: HistorySource<'_>,
+) -> Result<Vec<AddressBalance>, ClientError> {
+    match source {
+        HistorySource::LocalChain => {
+            let chain = read_chain().map_err(|err| ClientError::IoError(err.to_string()))?;
+            let mem = NodeMemory::replay(&chain)
+                .map_err(|err| ClientError::IoError(format!("{}", err)))?;
+            Ok(addresses
+                .iter()
+                .map(|address| AddressBalance {
+                    address: *address,
+                    balance: mem.balance_of(address),
+                    spendable: mem.spendable_balance_of(address, current_timestamp()),
+                })
+                .collect())
+        }
+        HistorySource::Node(node_url) => {
+            let mut client = connect(node_url).await?;
+            let mut balances = vec![];
+            for address in addresses.iter() {
+                let res = client
+                    .get_balance(Request::new(BalanceRequest {
+                        address: address.as_bytes().to_vec(),
+                        asset_id: vec![],
+                    }))
+                    .await
+                    .map_err(|status| ClientError::RequestError(status.message().to_string()))?
+                    .into_inner();
+                balances.push(AddressBalance {
+                    address: *address,
+                    balance: parse_u256_bytes(res.balance)?,
+                    spendable: parse_u256_bytes(res.spendable)?,
+                });
+            }
+            Ok(balances)
+        }
+    }
+}
+
+/// Writes the entries as CSV, quoting fields that need it.
+pub fn write_history_csv(
+    entries: &[HistoryEntry],
+    path: &str,
+    decimals: u8,
+) -> Result<(), ClientError> {
+    let mut content = format!("{HISTORY_CSV_HEADER}\n");
+    for entry in entries.iter() {
+        let row: Vec<String> = entry
+            .to_row(decimals)
+            .iter()
+            .map(|field| csv_field(field))
+            .collect();
+        content.push_str(&row.join(","));
+        content.push('\n');
+    }
+    fs::write(path, content).map_err(|err| ClientError::IoError(format!("{path}: {err}")))
+}
+
+// Memos come from whoever sent the transaction, a leading `'` keeps spreadsheets from
+// running them as formulas. A lone `-` is the empty counterparty and is left as is.
+fn csv_field(field: &str) -> String {
+    let field = match field.starts_with(['=', '+', '-', '@', '\t', '\r']) && field != "-" {
+        true => format!("'{field}"),
+        false => field.to_string(),
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+fn parse_address_bytes(bytes: Vec<u8>) -> Result<Address, ClientError> {
+    let bytes: [u8; 20] = bytes
+        .try_into()
+        .map_err(|_| ClientError::RequestError(String::from("Invalid address")))?;
+    Ok(Address::from(bytes))
+}
+
+fn parse_u256_bytes(bytes: Vec<u8>) -> Result<U256, ClientError> {
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| ClientError::RequestError(String::from("Invalid amount")))?;
+    Ok(U256::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::csv_field;
+
+    #[test]
+    fn csv_fields_cannot_start_a_formula() {
+        assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("-"), "-");
+        assert_eq!(csv_field("invoice 42, paid"), "\"invoice 42, paid\"");
+    }
+}
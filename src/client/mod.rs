@@ -0,0 +1,4 @@
+pub mod core;
+pub mod history;
+pub mod offline;
+pub mod partial;
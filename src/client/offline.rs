@@ -0,0 +1,157 @@
+use std::fs;
+
+use ethers::types::{Address, U256};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    transaction::core::Transaction,
+    utils::{input::Input, units::format_amount},
+    wallet::core::{ask_password, unlock_account},
+};
+
+use super::core::{broadcast_transaction, fetch_nonce, unsigned_transfer, ClientError};
+
+pub const OFFLINE_TX_FORMAT: &str = "zrush-offline-tx";
+pub const OFFLINE_TX_VERSION: u32 = 1;
+
+/// A transaction moved between machines as a pretty-printed JSON file:
+///
+/// - `format`: always `"zrush-offline-tx"`
+/// - `version`: layout of the file, readers reject versions they don't know
+/// - `nonce`: sender nonce the transaction is signed for, fetched by `tx create`
+/// - `signed`: whether `tx sign` already added the sender's signature
+/// - `signing_message`: exact text the sender signs, `:` separated. It holds the full
+///   hex addresses and the amount, nonce and fee in base units as decimal numbers,
+///   e.g. `zrush_signed_message:0x…01:0x…02:1500:3:fee:10`. `tx show` prints them
+///   with units for checking on the air-gapped machine before signing
+/// - `transaction`: the transaction as stored on chain, amounts are hex quantities
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OfflineTransaction {
+    format: String,
+    version: u32,
+    nonce: U256,
+    signed: bool,
+    signing_message: String,
+    transaction: Transaction,
+}
+
+impl OfflineTransaction {
+    pub fn new(transaction: Transaction, nonce: U256, signed: bool) -> OfflineTransaction {
+        OfflineTransaction {
+            format: String::from(OFFLINE_TX_FORMAT),
+            version: OFFLINE_TX_VERSION,
+            signing_message: transaction.signing_message(&nonce),
+            nonce,
+            signed,
+            transaction,
+        }
+    }
+
+    pub fn read(path: &str) -> Result<OfflineTransaction, ClientError> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| ClientError::IoError(format!("{path}: {err}")))?;
+        let file: OfflineTransaction = serde_json::from_str(&content)
+            .map_err(|err| ClientError::InvalidInput(format!("{path}: {err}")))?;
+        if file.format != OFFLINE_TX_FORMAT {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: not a zrush transaction file"
+            )));
+        }
+        if file.version != OFFLINE_TX_VERSION {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: unsupported version {}",
+                file.version
+            )));
+        }
+        // The message is only there for humans, never trust an edited one
+        if file.signing_message != file.transaction.signing_message(&file.nonce) {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: signing message does not match the transaction"
+            )));
+        }
+        Ok(file)
+    }
+
+    /// Lines printed by `tx show`, with full addresses so the receiver can be checked.
+    pub fn summary(&self, decimals: u8) -> Vec<String> {
+        let tx = &self.transaction;
+        let mut lines = vec![
+            format!("Kind:    {}", tx.kind().name()),
+            format!("From:    {:?}", tx.from()),
+        ];
+        for output in tx.outputs().iter() {
+            lines.push(format!(
+                "To:      {:?} {}",
+                output.to(),
+                format_amount(output.amount(), decimals)
+            ));
+        }
+        lines.push(format!(
+            "Fee:     {}",
+            format_amount(tx.fee_amount(), decimals)
+        ));
+        lines.push(format!("Nonce:   {}", self.nonce));
+        lines.push(format!("Signed:  {}", self.signed));
+        lines.push(format!("Message: {}", self.signing_message));
+        lines
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), ClientError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| ClientError::InvalidInput(err.to_string()))?;
+        fs::write(path, content).map_err(|err| ClientError::IoError(format!("{path}: {err}")))
+    }
+}
+
+/// Writes an unsigned transfer to `path`, using the nonce the node expects next.
+pub async fn create_offline_transfer(
+    from: Address,
+    to: Address,
+    amount: U256,
+    fee: U256,
+    node_url: &str,
+    path: &str,
+) -> Result<(), ClientError> {
+    let nonce = fetch_nonce(node_url, &from).await?;
+    OfflineTransaction::new(unsigned_transfer(from, to, amount, fee), nonce, false).write(path)
+}
+
+/// Signs the transaction in `path` with the wallet's account `index`. Needs no
+/// network access.
+pub fn sign_offline_transaction(
+    path: &str,
+    wallet: &str,
+    index: u32,
+    input: &Input,
+) -> Result<(), ClientError> {
+    let file = OfflineTransaction::read(path)?;
+    if file.signed {
+        return Err(ClientError::InvalidInput(format!("{path}: already signed")));
+    }
+    let password = ask_password(input).map_err(ClientError::WalletError)?;
+    let key = unlock_account(wallet, &password, index).map_err(ClientError::WalletError)?;
+    if &key.address() != file.transaction.from() {
+        return Err(ClientError::InvalidInput(format!(
+            "Wallet {wallet} does not own {:?}",
+            file.transaction.from()
+        )));
+    }
+    let tx = key
+        .sign_transaction(file.transaction, &file.nonce)
+        .map_err(ClientError::WalletError)?;
+    OfflineTransaction::new(tx, file.nonce, true).write(path)
+}
+
+/// Submits the signed transaction in `path` and returns its id.
+pub async fn broadcast_offline_transaction(
+    path: &str,
+    node_url: &str,
+) -> Result<String, ClientError> {
+    let file = OfflineTransaction::read(path)?;
+    if !file.signed {
+        return Err(ClientError::InvalidInput(format!(
+            "{path}: sign it with `tx sign` first"
+        )));
+    }
+    broadcast_transaction(node_url, &file.transaction).await
+}
@@ -0,0 +1,225 @@
+use std::fs;
+
+use ethers::types::{Address, Signature, U256};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    signature::{
+        multisig::MultisigAccount,
+        verification::{verify_multisig, verify_signature, KeyType},
+    },
+    transaction::core::Transaction,
+    utils::input::Input,
+    wallet::core::{ask_password, find_wallet, unlock_account},
+};
+
+use super::{
+    core::{fetch_multisig, fetch_nonce, unsigned_transfer, ClientError},
+    offline::OfflineTransaction,
+};
+
+pub const PARTIAL_TX_FORMAT: &str = "zrush-partial-tx";
+pub const PARTIAL_TX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialSignature {
+    signer: Address,
+    signature: Signature,
+}
+
+/// A multisig spend passed between cosigners as a pretty-printed JSON file:
+///
+/// - `format`: always `"zrush-partial-tx"`
+/// - `version`: layout of the file, readers reject versions they don't know
+/// - `nonce`: nonce of the multisig account the transaction is signed for
+/// - `signing_message`: exact text every cosigner signs
+/// - `account`: signer set and threshold registered on chain for the sender
+/// - `signatures`: signatures gathered so far, at most one per signer
+/// - `transaction`: the unsigned transaction, amounts are hex quantities
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PartialTransaction {
+    format: String,
+    version: u32,
+    nonce: U256,
+    signing_message: String,
+    account: MultisigAccount,
+    signatures: Vec<PartialSignature>,
+    transaction: Transaction,
+}
+
+impl PartialTransaction {
+    pub fn new(
+        transaction: Transaction,
+        nonce: U256,
+        account: MultisigAccount,
+    ) -> PartialTransaction {
+        PartialTransaction {
+            format: String::from(PARTIAL_TX_FORMAT),
+            version: PARTIAL_TX_VERSION,
+            signing_message: transaction.signing_message(&nonce),
+            nonce,
+            account,
+            signatures: vec![],
+            transaction,
+        }
+    }
+
+    /// Reads a container, checking every signature it holds.
+    pub fn read(path: &str) -> Result<PartialTransaction, ClientError> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| ClientError::IoError(format!("{path}: {err}")))?;
+        let file: PartialTransaction = serde_json::from_str(&content)
+            .map_err(|err| ClientError::InvalidInput(format!("{path}: {err}")))?;
+        if file.format != PARTIAL_TX_FORMAT {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: not a zrush partially signed transaction"
+            )));
+        }
+        if file.version != PARTIAL_TX_VERSION {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: unsupported version {}",
+                file.version
+            )));
+        }
+        if file.signing_message != file.transaction.signing_message(&file.nonce) {
+            return Err(ClientError::InvalidInput(format!(
+                "{path}: signing message does not match the transaction"
+            )));
+        }
+        for partial in file.signatures.iter() {
+            if !file.account.signers().contains(&partial.signer) {
+                return Err(ClientError::InvalidInput(format!(
+                    "{path}: {:?} is not a signer of the account",
+                    partial.signer
+                )));
+            }
+            verify_signature(&file.signing_message, &partial.signature, &partial.signer)
+                .map_err(|err| ClientError::InvalidInput(format!("{path}: {}", err)))?;
+        }
+        Ok(file)
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), ClientError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| ClientError::InvalidInput(err.to_string()))?;
+        fs::write(path, content).map_err(|err| ClientError::IoError(format!("{path}: {err}")))
+    }
+
+    fn has_signed(&self, signer: &Address) -> bool {
+        self.signatures
+            .iter()
+            .any(|partial| partial.signer == *signer)
+    }
+
+    fn push_signature(&mut self, partial: PartialSignature) {
+        if !self.has_signed(&partial.signer) {
+            self.signatures.push(partial);
+        }
+    }
+
+    /// The first `threshold` cosignatures in the order of the account's signer set,
+    /// the only set the node accepts.
+    fn cosignatures(&self) -> Vec<Signature> {
+        self.account
+            .signers()
+            .iter()
+            .filter_map(|signer| {
+                self.signatures
+                    .iter()
+                    .find(|partial| partial.signer == *signer)
+                    .map(|partial| partial.signature)
+            })
+            .take(self.account.threshold() as usize)
+            .collect()
+    }
+}
+
+/// Writes a transfer out of the multisig account at `from` with no signatures yet.
+pub async fn create_partial_transfer(
+    from: Address,
+    to: Address,
+    amount: U256,
+    fee: U256,
+    node_url: &str,
+    path: &str,
+) -> Result<(), ClientError> {
+    let account = fetch_multisig(node_url, &from).await?;
+    let nonce = fetch_nonce(node_url, &from).await?;
+    PartialTransaction::new(unsigned_transfer(from, to, amount, fee), nonce, account).write(path)
+}
+
+/// Adds the signature of the wallet's account `index` to the container in `path`.
+pub fn sign_partial_transaction(
+    path: &str,
+    wallet: &str,
+    index: u32,
+    input: &Input,
+) -> Result<(), ClientError> {
+    let mut file = PartialTransaction::read(path)?;
+    // Checked before asking for the password, the key could never sign anyway
+    if find_wallet(wallet)
+        .map_err(ClientError::WalletError)?
+        .is_some_and(|entry| entry.key_type() == KeyType::Ed25519)
+    {
+        return Err(ClientError::InvalidInput(format!(
+            "{wallet} is an ed25519 wallet, multisig members sign with secp256k1 keys"
+        )));
+    }
+    println!("Signing: {}", file.signing_message);
+
+    let password = ask_password(input).map_err(ClientError::WalletError)?;
+    let key = unlock_account(wallet, &password, index).map_err(ClientError::WalletError)?;
+    let signer = key.address();
+    if !file.account.signers().contains(&signer) {
+        return Err(ClientError::InvalidInput(format!(
+            "{:?} is not a signer of the account",
+            signer
+        )));
+    }
+    if file.has_signed(&signer) {
+        return Err(ClientError::InvalidInput(format!(
+            "{:?} already signed",
+            signer
+        )));
+    }
+    let signature = key
+        .sign_message(&file.signing_message)
+        .map_err(ClientError::WalletError)?;
+    file.push_signature(PartialSignature { signer, signature });
+    file.write(path)
+}
+
+/// Merges the signatures of containers holding the same transaction into `out`.
+pub fn combine_partial_transactions(paths: &[String], out: &str) -> Result<(), ClientError> {
+    let mut files = paths
+        .iter()
+        .map(|path| PartialTransaction::read(path))
+        .collect::<Result<Vec<PartialTransaction>, ClientError>>()?
+        .into_iter();
+    let mut combined = files.next().ok_or(ClientError::InvalidInput(String::from(
+        "Nothing to combine",
+    )))?;
+    for file in files {
+        if file.signing_message != combined.signing_message || file.account != combined.account {
+            return Err(ClientError::InvalidInput(String::from(
+                "Containers hold different transactions",
+            )));
+        }
+        for partial in file.signatures {
+            combined.push_signature(partial);
+        }
+    }
+    combined.write(out)
+}
+
+/// Turns a container that meets its threshold into a signed transaction file for
+/// `tx broadcast`.
+pub fn finalize_partial_transaction(path: &str, out: &str) -> Result<(), ClientError> {
+    let file = PartialTransaction::read(path)?;
+    let cosignatures = file.cosignatures();
+    verify_multisig(&file.signing_message, &cosignatures, &file.account)
+        .map_err(|err| ClientError::InvalidInput(format!("{}", err)))?;
+
+    let tx = file.transaction.with_cosignatures(cosignatures);
+    OfflineTransaction::new(tx, file.nonce, true).write(out)
+}
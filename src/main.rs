@@ -1,9 +1,12 @@
+mod asset;
 mod block;
+mod client;
 mod cli;
 mod node;
 mod signature;
 mod transaction;
 mod utils;
+mod vm;
 mod wallet;
 mod p2p;
 use cli::core::run_cli;
@@ -1,30 +1,63 @@
 use crate::{
+    asset::core::{AssetId, TokenMetadata},
     block::core::Block,
-    node::memory::NodeMemory,
-    signature::verification::verify_signature,
-    transaction::core::Transaction,
+    node::{
+        fees::{estimate_fee, DEFAULT_FEE_TARGETS, FEE_HISTORY_BLOCKS},
+        history::address_transactions,
+        memory::{Movement, NodeMemory},
+    },
+    signature::{
+        batch::SignatureCheck,
+        multisig::MultisigAccount,
+        verification::{KeyType, SignatureScheme},
+    },
+    transaction::{
+        core::{
+            Sponsorship, Transaction, TransactionKind, TransferOutput, FEE_PER_DATA_BYTE,
+            MAX_DATA_SIZE,
+        },
+        lock::LockSchedule,
+        receipt::TransactionStatus,
+    },
     utils::{
         banner::print_banner,
-        ethers_empty_types::ADDRESS_ZERO,
+        ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE},
         files::{read_from_file, write_to_file},
+        input::Input,
         timestamp::{current_timestamp, months_to_milliseconds},
+        units::{parse_amount, DEFAULT_DECIMALS, MAX_DECIMALS},
     },
     wallet::core::create_wallet,
     p2p::core::{connect_node, propagate_transaction, propagate_block}
 };
-use ethers::types::{Address, Signature, U256};
-use serde_derive::Serialize;
-use std::{io::stdin,sync::Mutex, thread};
+use ethers::types::{Address, Signature, H256, U256};
+use serde_derive::{Deserialize, Serialize};
+use std::{fmt, fs, path::Path,sync::Mutex, thread};
 use tokio::runtime;
 use tonic::{transport::Server, Code, Request, Response, Status};
 use super::node_proto::node_proto;
 
 
 use node_proto::{
+    allowance_operation::Action,
+    htlc_operation::Action as HtlcAction,
+    lock_schedule::Schedule,
     node_client::NodeClient,
     node_server::{Node, NodeServer},
-    AddBlockRequest, BlockResponse, NodeInfoRequest, RequestNodeInfoResponse, RequestSyncResponse,
-    SyncRequest, TransactionRequest, TransactionResponse,
+    token_operation::Operation,
+    AccountBalance, AddBlockRequest, AddressHistoryRequest, AddressHistoryResponse,
+    AllowanceOperation, AllowanceRequest, AllowanceResponse, BalanceRequest, BalanceResponse,
+    BlockByHashRequest, BlockByHeightRequest, BlockResponse, ChainHeadRequest, ChainHeadResponse,
+    ContractCall, ContractDeploy, Ed25519Signature as GrpcEd25519Signature, EstimateFeeRequest,
+    EstimateFeeResponse, FeeEstimate, GetBlockResponse, GetTransactionRequest,
+    GetTransactionResponse, HtlcClaim, HtlcLock as GrpcHtlcLock, HtlcOperation, HtlcRequest,
+    HtlcResponse, LockSchedule as GrpcLockSchedule, MultisigRequest, MultisigResponse,
+    MultisigSetup, NodeInfoRequest, NonceRequest, NonceResponse, RequestNodeInfoResponse,
+    RequestSyncResponse, Settlement, Signature as GrpcSignature, SimulateTransactionRequest,
+    SimulateTransactionResponse, Sponsorship as GrpcSponsorship, SyncRequest, TokenIssue,
+    TokenOperation, TransactionReceiptRequest, TransactionReceiptResponse, TransactionRequest,
+    TransactionResponse, TransactionStatus as GrpcTransactionStatus,
+    TransferOutput as GrpcTransferOutput, VestingSchedule,
 };
 
 
@@ -35,6 +68,15 @@ pub enum NodeError {
     InvalidConfigInput(String),
 }
 
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeError::SyncError(msg) | NodeError::InvalidConfigInput(msg) => write!(f, "{msg}"),
+            NodeError::NetworkError(msg) => write!(f, "Network error: {msg}"),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct NodeService {
     memory: Mutex<NodeMemory>,
@@ -58,36 +100,12 @@ impl Node for NodeService {
 
         println!("{:#?}", parsed_tx);
 
-        let from = parsed_tx.from();
-        let to = parsed_tx.to();
-        let amount = parsed_tx.amount();
         let mut mem = self.memory.lock().unwrap();
-
-        // Construct the message for signature verification
-        let nonce = mem.current_nonce(from);
-        let msg = format!("zrush_signed_message:{}{}{:?}{:?}", from, to, amount, nonce);
-
-        // Check transaction validity and signature
-        if !parsed_tx.verify(&mem) {
-            return Err(Status::new(
-                Code::InvalidArgument,
-                "Invalid transaction amount",
-            ));
-        } else if !verify_signature(&msg, parsed_tx.signature(), from) {
-            return Err(Status::new(Code::InvalidArgument, "Invalid signature"));
+        let tx_id = parsed_tx.id();
+        if let Err(reason) = execute_transaction(&mut mem, &parsed_tx) {
+            return Err(Status::new(Code::InvalidArgument, reason));
         }
 
-        // Update sender's balance and nonce
-        let sender_balance = mem.balance_of(from);
-        let new_balance = sender_balance.checked_sub(*amount).unwrap();
-        mem.set_balance(from, &new_balance);
-        mem.increment_nonce(from);
-
-        // Update receiver's balance
-        let receiver_balance = mem.balance_of(to);
-        let new_balance = receiver_balance.checked_add(*amount).unwrap();
-        mem.set_balance(to, &new_balance);
-
         // Push the transaction to the mempool
         mem.push_to_mempool(&parsed_tx);
 
@@ -109,7 +127,7 @@ impl Node for NodeService {
         handle.join().unwrap();
 
         // Return a successful response
-        Ok(Response::new(TransactionResponse {}))
+        Ok(Response::new(TransactionResponse { tx_id }))
     }
     async fn request_add_block(
         &self,
@@ -138,13 +156,25 @@ impl Node for NodeService {
         });
 
         handle.join().unwrap();
-        match block.validate(&mem) {
+        // The node memory already holds the mempool, signatures are checked against
+        // the state confirmed by the stored chain
+        let mut chain = read_chain().map_err(|err| Status::new(Code::DataLoss, err))?;
+        let mut confirmed = NodeMemory::replay(&chain)
+            .map_err(|err| Status::new(Code::DataLoss, format!("{}", err)))?;
+        match block.validate(&confirmed, mem.verified_cache()) {
             true => {
-                let chain = read_from_file("data/storage", "chain_data.json").unwrap();
-                let mut chain: Vec<Block> = serde_json::from_str(&chain).unwrap();
+                // A stored block that doesn't apply would stop every later replay, so
+                // its transactions are run on the replayed copy before it is written
+                confirmed
+                    .process_block(&block)
+                    .map_err(|err| Status::new(Code::InvalidArgument, format!("{}", err)))?;
+                mem.include_block(&block);
+                // Drops what expired or included transactions did to the pending state and
+                // applies block transactions this node never saw
+                mem.rebase(confirmed);
                 chain.push(block);
                 let content = serde_json::to_string(&chain).unwrap();
-                write_to_file("data/", "chain_data.json", &content).unwrap();
+                write_to_file("data/storage", "chain_data.json", &content).unwrap();
                 return Ok(Response::new(BlockResponse {}));
             }
             false => {
@@ -195,6 +225,544 @@ impl Node for NodeService {
             address: self.memory.lock().unwrap().node_address(),
         }))
     }
+
+    async fn get_transaction_receipt(
+        &self,
+        req: Request<TransactionReceiptRequest>,
+    ) -> Result<Response<TransactionReceiptResponse>, Status> {
+        let tx_id = req.into_inner().tx_id;
+        let status = self
+            .memory
+            .lock()
+            .unwrap()
+            .transaction_status(&tx_id)
+            .ok_or(Status::new(Code::NotFound, "Unknown transaction"))?;
+
+        Ok(Response::new(receipt_to_grpc(status)))
+    }
+
+    async fn get_balance(
+        &self,
+        req: Request<BalanceRequest>,
+    ) -> Result<Response<BalanceResponse>, Status> {
+        let req = req.into_inner();
+        let address = parse_grpc_address(req.address)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let mem = self.memory.lock().unwrap();
+
+        // Only the native coin can be time locked
+        if !req.asset_id.is_empty() {
+            let asset_id = parse_grpc_asset_id(req.asset_id)
+                .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+            let balance = u256_to_bytes(&mem.asset_balance_of(&asset_id, &address));
+            return Ok(Response::new(BalanceResponse {
+                balance: balance.clone(),
+                spendable: balance,
+            }));
+        }
+
+        Ok(Response::new(BalanceResponse {
+            balance: u256_to_bytes(&mem.balance_of(&address)),
+            spendable: u256_to_bytes(&mem.spendable_balance_of(&address, current_timestamp())),
+        }))
+    }
+
+    async fn get_nonce(
+        &self,
+        req: Request<NonceRequest>,
+    ) -> Result<Response<NonceResponse>, Status> {
+        let address = parse_grpc_address(req.into_inner().address)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let nonce = self.memory.lock().unwrap().current_nonce(&address);
+        Ok(Response::new(NonceResponse {
+            nonce: u256_to_bytes(&nonce),
+        }))
+    }
+
+    async fn get_allowance(
+        &self,
+        req: Request<AllowanceRequest>,
+    ) -> Result<Response<AllowanceResponse>, Status> {
+        let req = req.into_inner();
+        let owner =
+            parse_grpc_address(req.owner).map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let spender = parse_grpc_address(req.spender)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let amount = self.memory.lock().unwrap().allowance(&owner, &spender);
+        Ok(Response::new(AllowanceResponse {
+            amount: u256_to_bytes(&amount),
+        }))
+    }
+
+    async fn get_htlc(&self, req: Request<HtlcRequest>) -> Result<Response<HtlcResponse>, Status> {
+        let id = parse_grpc_hash(req.into_inner().id)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let htlc = self
+            .memory
+            .lock()
+            .unwrap()
+            .htlc(&id)
+            .ok_or(Status::new(Code::NotFound, "Unknown HTLC"))?;
+        Ok(Response::new(HtlcResponse {
+            sender: htlc.sender().as_bytes().to_vec(),
+            recipient: htlc.recipient().as_bytes().to_vec(),
+            amount: u256_to_bytes(htlc.amount()),
+            hash_lock: htlc.hash_lock().as_bytes().to_vec(),
+            timeout: htlc.timeout(),
+        }))
+    }
+
+    async fn get_multisig(
+        &self,
+        req: Request<MultisigRequest>,
+    ) -> Result<Response<MultisigResponse>, Status> {
+        let address = parse_grpc_address(req.into_inner().address)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let account = self
+            .memory
+            .lock()
+            .unwrap()
+            .multisig(&address)
+            .ok_or(Status::new(Code::NotFound, "Not a multisig account"))?;
+        Ok(Response::new(MultisigResponse {
+            account: Some(multisig_to_grpc(&account)),
+        }))
+    }
+
+    async fn get_block_by_hash(
+        &self,
+        req: Request<BlockByHashRequest>,
+    ) -> Result<Response<GetBlockResponse>, Status> {
+        let hash = req.into_inner().hash;
+        let block = read_chain()
+            .map_err(|err| Status::new(Code::DataLoss, err))?
+            .into_iter()
+            .find(|block| *block.hash() == hash)
+            .ok_or(Status::new(Code::NotFound, "Unknown block"))?;
+        Ok(Response::new(GetBlockResponse {
+            block: Some(block_to_grpc(&block)),
+        }))
+    }
+
+    async fn get_block_by_height(
+        &self,
+        req: Request<BlockByHeightRequest>,
+    ) -> Result<Response<GetBlockResponse>, Status> {
+        let height: [u8; 32] = req
+            .into_inner()
+            .height
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "Invalid height"))?;
+        let height = U256::from(height);
+        let block = read_chain()
+            .map_err(|err| Status::new(Code::DataLoss, err))?
+            .into_iter()
+            .find(|block| *block.height() == height)
+            .ok_or(Status::new(Code::NotFound, "Unknown block"))?;
+        Ok(Response::new(GetBlockResponse {
+            block: Some(block_to_grpc(&block)),
+        }))
+    }
+
+    async fn get_transaction(
+        &self,
+        req: Request<GetTransactionRequest>,
+    ) -> Result<Response<GetTransactionResponse>, Status> {
+        let tx_id = req.into_inner().tx_id;
+        let (tx, status, settlements) = {
+            let mem = self.memory.lock().unwrap();
+            (
+                mem.mempool_transaction(&tx_id),
+                mem.transaction_status(&tx_id),
+                mem.settlements(&tx_id),
+            )
+        };
+
+        // Pending transactions only live in the mempool, the rest are on chain
+        let tx = match tx {
+            Some(tx) => tx,
+            None => read_chain()
+                .map_err(|err| Status::new(Code::DataLoss, err))?
+                .iter()
+                .flat_map(|block| block.transactions().iter())
+                .find(|tx| tx.id() == tx_id)
+                .cloned()
+                .ok_or(Status::new(Code::NotFound, "Unknown transaction"))?,
+        };
+
+        Ok(Response::new(GetTransactionResponse {
+            transaction: Some(transaction_to_grpc(&tx)),
+            timestamp: tx.timestamp(),
+            receipt: status.map(receipt_to_grpc),
+            tx_id,
+            settlements: settlements_to_grpc(&settlements),
+        }))
+    }
+
+    async fn get_address_history(
+        &self,
+        req: Request<AddressHistoryRequest>,
+    ) -> Result<Response<AddressHistoryResponse>, Status> {
+        let addresses = req
+            .into_inner()
+            .addresses
+            .into_iter()
+            .map(parse_grpc_address)
+            .collect::<Result<Vec<Address>, &'static str>>()
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+        let chain = read_chain().map_err(|err| Status::new(Code::DataLoss, err))?;
+        let head = chain
+            .last()
+            .ok_or(Status::new(Code::DataLoss, "Empty chain"))?;
+        let mem = self.memory.lock().unwrap();
+        let pending = mem.mempool_transactions();
+
+        let transactions = address_transactions(&chain, &pending, &mem, &addresses)
+            .into_iter()
+            .map(|(tx, status)| GetTransactionResponse {
+                transaction: Some(transaction_to_grpc(&tx)),
+                timestamp: tx.timestamp(),
+                receipt: Some(receipt_to_grpc(status)),
+                settlements: settlements_to_grpc(&mem.settlements(&tx.id())),
+                tx_id: tx.id(),
+            })
+            .collect();
+        Ok(Response::new(AddressHistoryResponse {
+            transactions,
+            head_height: u256_to_bytes(head.height()),
+        }))
+    }
+
+    async fn get_chain_head(
+        &self,
+        _req: Request<ChainHeadRequest>,
+    ) -> Result<Response<ChainHeadResponse>, Status> {
+        let chain = read_chain().map_err(|err| Status::new(Code::DataLoss, err))?;
+        let head = chain
+            .last()
+            .ok_or(Status::new(Code::DataLoss, "Empty chain"))?;
+        Ok(Response::new(ChainHeadResponse {
+            hash: head.hash().clone(),
+            height: u256_to_bytes(head.height()),
+            timestamp: head.header().timestamp,
+            decimals: read_chain_config().map(|config| config.decimals() as u32),
+        }))
+    }
+
+    async fn simulate_transaction(
+        &self,
+        req: Request<SimulateTransactionRequest>,
+    ) -> Result<Response<SimulateTransactionResponse>, Status> {
+        let req = req.into_inner();
+        let tx = req
+            .transaction
+            .ok_or("Missing transaction")
+            .and_then(parse_grpc_transaction_request)
+            .map_err(|err| Status::new(Code::InvalidArgument, err))?;
+
+        // Run against a throwaway copy so the node state is never touched
+        let mut state = if req.pending {
+            self.memory.lock().unwrap().snapshot()
+        } else {
+            let chain = read_chain().map_err(|err| Status::new(Code::DataLoss, err))?;
+            NodeMemory::replay(&chain)
+                .map_err(|err| Status::new(Code::DataLoss, format!("{}", err)))?
+        };
+        let result = execute_transaction(&mut state, &tx);
+
+        let mut accounts = vec![*tx.from(), *tx.fee_payer()];
+        accounts.extend(tx.outputs().iter().map(|output| *output.to()));
+        if *tx.fee_receiver() != ADDRESS_ZERO() {
+            accounts.push(*tx.fee_receiver());
+        }
+        accounts.sort();
+        accounts.dedup();
+
+        let fee = match result {
+            Ok(_) => *tx.fee_amount(),
+            Err(_) => U256::from(0),
+        };
+        Ok(Response::new(SimulateTransactionResponse {
+            accepted: result.is_ok(),
+            reason: result.err().unwrap_or_default(),
+            fee: u256_to_bytes(&fee),
+            balances: accounts
+                .iter()
+                .map(|account| AccountBalance {
+                    address: account.as_bytes().to_vec(),
+                    balance: u256_to_bytes(&state.balance_of(account)),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn estimate_fee(
+        &self,
+        req: Request<EstimateFeeRequest>,
+    ) -> Result<Response<EstimateFeeResponse>, Status> {
+        let req = req.into_inner();
+        let targets = match req.target_blocks.is_empty() {
+            true => DEFAULT_FEE_TARGETS.to_vec(),
+            false => req.target_blocks,
+        };
+
+        let chain = read_chain().map_err(|err| Status::new(Code::DataLoss, err))?;
+        let recent_blocks = &chain[chain.len().saturating_sub(FEE_HISTORY_BLOCKS)..];
+        let mempool_fees = self.memory.lock().unwrap().mempool_fees();
+        // Memo bytes are charged on top of whatever the network asks for
+        let data_fee = U256::from(req.data_size) * U256::from(FEE_PER_DATA_BYTE);
+
+        let estimates = targets
+            .iter()
+            .map(|target| {
+                let fee = estimate_fee(recent_blocks, &mempool_fees, *target);
+                FeeEstimate {
+                    target_blocks: *target,
+                    fee: u256_to_bytes(&fee.saturating_add(data_fee)),
+                }
+            })
+            .collect();
+        Ok(Response::new(EstimateFeeResponse { estimates }))
+    }
+}
+
+/// Checks the transaction amount, fee and signatures against `mem` and, when valid,
+/// updates sender, receivers and nonce in one step.
+// Only correctly signed transactions get a failure receipt, so nobody can fill the
+// receipts with junk they never had to sign
+fn execute_transaction(mem: &mut NodeMemory, tx: &Transaction) -> Result<(), String> {
+    let from = tx.from();
+
+    // Construct the message for signature verification
+    let nonce = mem.current_nonce(from);
+    tx.signature_checks(&nonce, mem.multisig(from))
+        .iter()
+        .try_for_each(SignatureCheck::verify)
+        .map_err(|err| format!("Invalid signature: {}", err))?;
+
+    let res = match tx.verify(mem) {
+        false => Err(String::from("Invalid transaction amount or fee")),
+        true => mem
+            .apply_transaction(&ADDRESS_ZERO(), tx, tx.timestamp())
+            .map_err(|err| format!("{}", err)),
+    };
+    if let Err(reason) = &res {
+        mem.record_failure(&tx.id(), reason.clone());
+        return res;
+    }
+    // Spares checking the signature again when the block including it arrives
+    mem.verified_cache().insert(tx.id(), nonce);
+    Ok(())
+}
+
+pub fn read_chain() -> Result<Vec<Block>, &'static str> {
+    let chain =
+        read_from_file("data/storage", "chain_data.json").map_err(|_| "Error reading data")?;
+    serde_json::from_str(&chain).map_err(|_| "Error reading data")
+}
+
+fn parse_grpc_address(address: Vec<u8>) -> Result<Address, &'static str> {
+    let address: [u8; 20] = address.try_into().map_err(|_| "Invalid address")?;
+    Ok(Address::from(address))
+}
+
+fn receipt_to_grpc(status: TransactionStatus) -> TransactionReceiptResponse {
+    let mut receipt = TransactionReceiptResponse::default();
+    match status {
+        TransactionStatus::Pending => receipt.set_status(GrpcTransactionStatus::Pending),
+        TransactionStatus::Included {
+            block_hash,
+            height,
+            index,
+        } => {
+            receipt.set_status(GrpcTransactionStatus::Included);
+            receipt.block_hash = block_hash;
+            receipt.height = u256_to_bytes(&height);
+            receipt.index = index;
+        }
+        TransactionStatus::Dropped => receipt.set_status(GrpcTransactionStatus::Dropped),
+        TransactionStatus::Failed(reason) => {
+            receipt.set_status(GrpcTransactionStatus::Failed);
+            receipt.reason = reason;
+        }
+    }
+    receipt
+}
+
+fn block_to_grpc(block: &Block) -> AddBlockRequest {
+    AddBlockRequest {
+        hash: block.hash().clone(),
+        timestamp: block.header().timestamp,
+        nonce: u256_to_bytes(block.nonce()),
+        pre_hash: block.pre_hash().clone(),
+        merkle: block.merkle().clone(),
+        difficulty: u256_to_bytes(block.difficulty()),
+        height: u256_to_bytes(block.height()),
+        reward: u256_to_bytes(block.reward()),
+        transactions: block
+            .transactions()
+            .iter()
+            .map(transaction_to_grpc)
+            .collect(),
+    }
+}
+
+fn multisig_to_grpc(account: &MultisigAccount) -> MultisigSetup {
+    MultisigSetup {
+        signers: account
+            .signers()
+            .iter()
+            .map(|signer| signer.as_bytes().to_vec())
+            .collect(),
+        threshold: account.threshold() as u32,
+    }
+}
+
+fn ed25519_to_grpc(scheme: &SignatureScheme) -> Option<GrpcEd25519Signature> {
+    match scheme {
+        SignatureScheme::Secp256k1 => None,
+        SignatureScheme::Ed25519 {
+            public_key,
+            signature,
+        } => Some(GrpcEd25519Signature {
+            public_key: public_key.clone(),
+            signature: signature.clone(),
+        }),
+    }
+}
+
+pub fn transaction_to_grpc(tx: &Transaction) -> TransactionRequest {
+    let mut req = TransactionRequest {
+        from: tx.from().as_bytes().to_vec(),
+        to: tx.to().as_bytes().to_vec(),
+        amount: u256_to_bytes(tx.amount()),
+        fee: u256_to_bytes(tx.fee_amount()),
+        fee_receiver: tx.fee_receiver().as_bytes().to_vec(),
+        signature: Some(signature_to_grpc(tx.signature())),
+        cosignatures: tx.cosignatures().iter().map(signature_to_grpc).collect(),
+        data: tx.data().clone(),
+        ed25519: ed25519_to_grpc(tx.scheme()),
+        sponsorship: tx.sponsorship().map(|sponsorship| GrpcSponsorship {
+            sponsor: sponsorship.sponsor().as_bytes().to_vec(),
+            signature: Some(signature_to_grpc(sponsorship.signature())),
+            ed25519: ed25519_to_grpc(sponsorship.scheme()),
+        }),
+        ..Default::default()
+    };
+    match tx.kind() {
+        TransactionKind::Transfer => {}
+        TransactionKind::BatchTransfer(outputs) => {
+            req.outputs = outputs
+                .iter()
+                .map(|output| GrpcTransferOutput {
+                    to: output.to().as_bytes().to_vec(),
+                    amount: u256_to_bytes(output.amount()),
+                })
+                .collect();
+        }
+        TransactionKind::CreateMultisig(account) => {
+            req.multisig = Some(multisig_to_grpc(account));
+        }
+        TransactionKind::LockedTransfer(schedule) => {
+            let schedule = match schedule {
+                LockSchedule::UntilTimestamp(timestamp) => Schedule::UntilTimestamp(*timestamp),
+                LockSchedule::UntilHeight(height) => Schedule::UntilHeight(u256_to_bytes(height)),
+                LockSchedule::Vesting { start, cliff, end } => Schedule::Vesting(VestingSchedule {
+                    start: *start,
+                    cliff: *cliff,
+                    end: *end,
+                }),
+            };
+            req.lock = Some(GrpcLockSchedule {
+                schedule: Some(schedule),
+            });
+        }
+        TransactionKind::IssueToken { metadata, supply } => {
+            req.token = Some(TokenOperation {
+                operation: Some(Operation::Issue(TokenIssue {
+                    supply: u256_to_bytes(supply),
+                    name: metadata.name().clone(),
+                    symbol: metadata.symbol().clone(),
+                })),
+            });
+        }
+        TransactionKind::TransferToken(asset_id) => {
+            req.token = Some(TokenOperation {
+                operation: Some(Operation::Transfer(asset_id.as_bytes().to_vec())),
+            });
+        }
+        TransactionKind::BurnToken(asset_id) => {
+            req.token = Some(TokenOperation {
+                operation: Some(Operation::Burn(asset_id.as_bytes().to_vec())),
+            });
+        }
+        TransactionKind::DeployContract(code) => {
+            req.deploy = Some(ContractDeploy { code: code.clone() });
+        }
+        TransactionKind::CallContract { input, gas_limit } => {
+            req.call = Some(ContractCall {
+                input: input.clone(),
+                gas_limit: *gas_limit,
+            });
+        }
+        TransactionKind::Approve(spender) => {
+            req.allowance = Some(AllowanceOperation {
+                action: Some(Action::Approve(spender.as_bytes().to_vec())),
+            });
+        }
+        TransactionKind::TransferFrom(owner) => {
+            req.allowance = Some(AllowanceOperation {
+                action: Some(Action::TransferFrom(owner.as_bytes().to_vec())),
+            });
+        }
+        TransactionKind::HtlcLock { hash_lock, timeout } => {
+            req.htlc = Some(HtlcOperation {
+                action: Some(HtlcAction::Lock(GrpcHtlcLock {
+                    hash_lock: hash_lock.as_bytes().to_vec(),
+                    timeout: *timeout,
+                })),
+            });
+        }
+        TransactionKind::HtlcClaim { id, preimage } => {
+            req.htlc = Some(HtlcOperation {
+                action: Some(HtlcAction::Claim(HtlcClaim {
+                    id: id.as_bytes().to_vec(),
+                    preimage: preimage.clone(),
+                })),
+            });
+        }
+        TransactionKind::HtlcRefund(id) => {
+            req.htlc = Some(HtlcOperation {
+                action: Some(HtlcAction::Refund(id.as_bytes().to_vec())),
+            });
+        }
+    }
+    req
+}
+
+fn signature_to_grpc(signature: &Signature) -> GrpcSignature {
+    GrpcSignature {
+        r: u256_to_bytes(&signature.r),
+        s: u256_to_bytes(&signature.s),
+        v: signature.v,
+    }
+}
+
+fn settlements_to_grpc(settlements: &[Movement]) -> Vec<Settlement> {
+    settlements
+        .iter()
+        .map(|(from, to, amount)| Settlement {
+            from: from.as_bytes().to_vec(),
+            to: to.as_bytes().to_vec(),
+            amount: u256_to_bytes(amount),
+        })
+        .collect()
+}
+
+fn u256_to_bytes(value: &U256) -> Vec<u8> {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes.to_vec()
 }
 
 fn parse_grpc_block_request(block: AddBlockRequest) -> Result<Block, &'static str> {
@@ -217,7 +785,10 @@ fn parse_grpc_block_request(block: AddBlockRequest) -> Result<Block, &'static st
     let parsed_transactions: Result<Vec<Transaction>, &'static str> = block
         .transactions
         .iter()
-        .map(|tx| parse_grpc_transaction_request(tx.clone()).map_err(|err| err))
+        .map(|tx| {
+            // Stamped with the block's time, not the time this node received it
+            parse_grpc_transaction_request(tx.clone()).map(|tx| tx.with_timestamp(timestamp))
+        })
         .collect();
 
     Ok(Block::new(
@@ -233,31 +804,287 @@ fn parse_grpc_block_request(block: AddBlockRequest) -> Result<Block, &'static st
     ))
 }
 
-fn parse_grpc_transaction_request(tx: TransactionRequest) -> Result<Transaction, &'static str> {
+pub fn parse_grpc_transaction_request(
+    mut tx: TransactionRequest,
+) -> Result<Transaction, &'static str> {
+    let sponsorship = tx.sponsorship.take();
+    let scheme = match tx.ed25519.take() {
+        Some(ed25519) => {
+            // The ECDSA signature is unused for ed25519 senders. It is part of the tx id,
+            // so whatever was sent in its place is dropped
+            tx.signature = Some(signature_to_grpc(&EMPTY_SIGNATURE()));
+            SignatureScheme::Ed25519 {
+                public_key: ed25519.public_key,
+                signature: ed25519.signature,
+            }
+        }
+        None => SignatureScheme::Secp256k1,
+    };
+    let parsed = parse_grpc_transaction_body(tx)?.with_scheme(scheme);
+    match sponsorship {
+        Some(sponsorship) => Ok(parsed.with_sponsorship(parse_grpc_sponsorship(sponsorship)?)),
+        None => Ok(parsed),
+    }
+}
+
+fn parse_grpc_sponsorship(sponsorship: GrpcSponsorship) -> Result<Sponsorship, &'static str> {
+    let sponsor = parse_grpc_address(sponsorship.sponsor).map_err(|_| "Invalid sponsor")?;
+    // Like for senders, the ECDSA signature is unused for ed25519 sponsors
+    if let Some(ed25519) = sponsorship.ed25519 {
+        return Ok(Sponsorship::new(sponsor, EMPTY_SIGNATURE()).with_scheme(
+            SignatureScheme::Ed25519 {
+                public_key: ed25519.public_key,
+                signature: ed25519.signature,
+            },
+        ));
+    }
+    let signature =
+        parse_grpc_signature(sponsorship.signature.ok_or("Missing sponsor signature")?)?;
+    Ok(Sponsorship::new(sponsor, signature))
+}
+
+fn parse_grpc_transaction_body(tx: TransactionRequest) -> Result<Transaction, &'static str> {
     let from: [u8; 20] = tx.from.try_into().map_err(|_| "Invalid from")?;
-    let to: [u8; 20] = tx.to.try_into().map_err(|_| "Invalid to")?;
-    let amount: [u8; 32] = tx.amount.try_into().map_err(|_| "Invalid amount")?;
     let fee: [u8; 32] = tx.fee.try_into().map_err(|_| "Invalid fee")?;
-    let signature = tx.signature.unwrap();
+    if tx.data.len() > MAX_DATA_SIZE {
+        return Err("Data payload too large");
+    }
+    let data = tx.data;
+    // Multisig spends are signed by the cosigners alone, and an unused signature left
+    // in would let relayers change the tx id
+    let signature = match tx.cosignatures.is_empty() {
+        true => parse_grpc_signature(tx.signature.ok_or("Missing signature")?)?,
+        false => EMPTY_SIGNATURE(),
+    };
+    let cosignatures = tx
+        .cosignatures
+        .into_iter()
+        .map(parse_grpc_signature)
+        .collect::<Result<Vec<Signature>, &'static str>>()?;
 
-    let r: [u8; 32] = signature.r.try_into().map_err(|_| "Invalid r")?;
-    let s: [u8; 32] = signature.s.try_into().map_err(|_| "Invalid s")?;
-    let r = U256::from(r);
-    let s = U256::from(s);
-    let v = signature.v;
+    if let Some(setup) = tx.multisig {
+        return Ok(Transaction::create_multisig(
+            Address::from(from),
+            parse_grpc_multisig_setup(setup)?,
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_data(data));
+    }
+
+    if !tx.outputs.is_empty() {
+        let outputs = tx
+            .outputs
+            .into_iter()
+            .map(parse_grpc_transfer_output)
+            .collect::<Result<Vec<TransferOutput>, &'static str>>()?;
+        return Ok(Transaction::batch_transfer(
+            Address::from(from),
+            outputs,
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_cosignatures(cosignatures)
+        .with_data(data));
+    }
+
+    if let Some(TokenOperation {
+        operation: Some(Operation::Issue(issue)),
+    }) = tx.token
+    {
+        let supply: [u8; 32] = issue
+            .supply
+            .try_into()
+            .map_err(|_| "Invalid token supply")?;
+        return Ok(Transaction::new(
+            Address::from(from),
+            ADDRESS_ZERO(),
+            U256::from(0),
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_kind(TransactionKind::IssueToken {
+            metadata: TokenMetadata::new(issue.name, issue.symbol),
+            supply: U256::from(supply),
+        })
+        .with_cosignatures(cosignatures)
+        .with_data(data));
+    }
+
+    if let Some(deploy) = tx.deploy {
+        return Ok(Transaction::new(
+            Address::from(from),
+            ADDRESS_ZERO(),
+            U256::from(0),
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_kind(TransactionKind::DeployContract(deploy.code))
+        .with_cosignatures(cosignatures)
+        .with_data(data));
+    }
+
+    if let Some(action) = tx.htlc.and_then(|htlc| htlc.action) {
+        let kind = match action {
+            HtlcAction::Lock(lock) => TransactionKind::HtlcLock {
+                hash_lock: parse_grpc_hash(lock.hash_lock)?,
+                timeout: lock.timeout,
+            },
+            HtlcAction::Claim(claim) => TransactionKind::HtlcClaim {
+                id: parse_grpc_hash(claim.id)?,
+                preimage: claim.preimage,
+            },
+            HtlcAction::Refund(id) => TransactionKind::HtlcRefund(parse_grpc_hash(id)?),
+        };
+        // Only locking moves funds out of the sender
+        let (to, amount) = match kind {
+            TransactionKind::HtlcLock { .. } => {
+                let to: [u8; 20] = tx.to.try_into().map_err(|_| "Invalid to")?;
+                let amount: [u8; 32] = tx.amount.try_into().map_err(|_| "Invalid amount")?;
+                (Address::from(to), U256::from(amount))
+            }
+            _ => (ADDRESS_ZERO(), U256::from(0)),
+        };
+        return Ok(Transaction::new(
+            Address::from(from),
+            to,
+            amount,
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_kind(kind)
+        .with_cosignatures(cosignatures)
+        .with_data(data));
+    }
+
+    let action = tx.allowance.and_then(|allowance| allowance.action);
+    if let Some(Action::Approve(spender)) = action {
+        let amount: [u8; 32] = tx.amount.try_into().map_err(|_| "Invalid amount")?;
+        return Ok(Transaction::new(
+            Address::from(from),
+            ADDRESS_ZERO(),
+            U256::from(amount),
+            U256::from(fee),
+            signature,
+            current_timestamp(),
+            ADDRESS_ZERO(),
+        )
+        .with_kind(TransactionKind::Approve(parse_grpc_address(spender)?))
+        .with_cosignatures(cosignatures)
+        .with_data(data));
+    }
+
+    let operation = tx.token.and_then(|token| token.operation);
+    let to: [u8; 20] = match (&operation, tx.to.is_empty()) {
+        // Burning has no receiver
+        (Some(Operation::Burn(_)), true) => [0u8; 20],
+        _ => tx.to.try_into().map_err(|_| "Invalid to")?,
+    };
+    let amount: [u8; 32] = tx.amount.try_into().map_err(|_| "Invalid amount")?;
+    let kind = match (tx.lock, operation, tx.call, action) {
+        (Some(lock), _, _, _) => TransactionKind::LockedTransfer(parse_grpc_lock_schedule(lock)?),
+        (None, Some(Operation::Transfer(asset_id)), _, _) => {
+            TransactionKind::TransferToken(parse_grpc_asset_id(asset_id)?)
+        }
+        (None, Some(Operation::Burn(asset_id)), _, _) => {
+            TransactionKind::BurnToken(parse_grpc_asset_id(asset_id)?)
+        }
+        (None, None, Some(call), _) => TransactionKind::CallContract {
+            input: call.input,
+            gas_limit: call.gas_limit,
+        },
+        (None, None, None, Some(Action::TransferFrom(owner))) => {
+            TransactionKind::TransferFrom(parse_grpc_address(owner)?)
+        }
+        _ => TransactionKind::Transfer,
+    };
 
     Ok(Transaction::new(
         Address::from(from),
         Address::from(to),
         U256::from(amount),
         U256::from(fee),
-        Signature { r, s, v },
+        signature,
         current_timestamp(),
         ADDRESS_ZERO(),
-    ))
+    )
+    .with_kind(kind)
+    .with_cosignatures(cosignatures)
+    .with_data(data))
+}
+
+fn parse_grpc_asset_id(asset_id: Vec<u8>) -> Result<AssetId, &'static str> {
+    let asset_id: [u8; 32] = asset_id.try_into().map_err(|_| "Invalid asset id")?;
+    Ok(AssetId::from(asset_id))
+}
+
+fn parse_grpc_hash(hash: Vec<u8>) -> Result<H256, &'static str> {
+    let hash: [u8; 32] = hash.try_into().map_err(|_| "Invalid hash")?;
+    Ok(H256::from(hash))
+}
+
+fn parse_grpc_lock_schedule(lock: GrpcLockSchedule) -> Result<LockSchedule, &'static str> {
+    match lock.schedule.ok_or("Missing lock schedule")? {
+        Schedule::UntilTimestamp(timestamp) => Ok(LockSchedule::UntilTimestamp(timestamp)),
+        Schedule::UntilHeight(height) => {
+            let height: [u8; 32] = height.try_into().map_err(|_| "Invalid lock height")?;
+            Ok(LockSchedule::UntilHeight(U256::from(height)))
+        }
+        Schedule::Vesting(vesting) => Ok(LockSchedule::Vesting {
+            start: vesting.start,
+            cliff: vesting.cliff,
+            end: vesting.end,
+        }),
+    }
+}
+
+fn parse_grpc_signature(signature: GrpcSignature) -> Result<Signature, &'static str> {
+    let r: [u8; 32] = signature.r.try_into().map_err(|_| "Invalid r")?;
+    let s: [u8; 32] = signature.s.try_into().map_err(|_| "Invalid s")?;
+    let r = U256::from(r);
+    let s = U256::from(s);
+    let v = signature.v;
+
+    Ok(Signature { r, s, v })
+}
+
+pub fn parse_grpc_multisig_setup(setup: MultisigSetup) -> Result<MultisigAccount, &'static str> {
+    let signers = setup
+        .signers
+        .into_iter()
+        .map(|signer| {
+            let signer: [u8; 20] = signer.try_into().map_err(|_| "Invalid multisig signer")?;
+            Ok(Address::from(signer))
+        })
+        .collect::<Result<Vec<Address>, &'static str>>()?;
+    let threshold: u8 = setup
+        .threshold
+        .try_into()
+        .map_err(|_| "Invalid multisig threshold")?;
+
+    Ok(MultisigAccount::new(signers, threshold))
 }
 
-#[derive(Debug, Default, Serialize, Clone)]
+fn parse_grpc_transfer_output(output: GrpcTransferOutput) -> Result<TransferOutput, &'static str> {
+    let to: [u8; 20] = output.to.try_into().map_err(|_| "Invalid output to")?;
+    let amount: [u8; 32] = output
+        .amount
+        .try_into()
+        .map_err(|_| "Invalid output amount")?;
+    Ok(TransferOutput::new(Address::from(to), U256::from(amount)))
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct ChainConfig {
     name: String,
     chain_id: u8,
@@ -265,19 +1092,50 @@ pub struct ChainConfig {
     creation_timestamp: u64,
     seconds_between_blocks: u8,
     months_between_halvings: u64,
+    // Chains created before decimals existed count in base units
+    #[serde(default)]
+    decimals: u8,
+}
+
+impl ChainConfig {
+    /// Decimal places between one coin and the base unit amounts are stored in.
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+}
+
+/// The config in `data/chain_config.json`, `None` when this machine has no chain.
+pub fn read_chain_config() -> Option<ChainConfig> {
+    let content = fs::read_to_string("data/chain_config.json").ok()?;
+    serde_json::from_str(&content).ok()
 }
 
-pub fn create_new_blockchain() -> Result<(), NodeError> {
+/// Creates the genesis block, answering the setup questions from `input` or stdin.
+pub fn create_new_blockchain(input: &Input) -> Result<(), NodeError> {
     print_banner();
     println!("Welcome to zrush v1.1\n");
 
-    let (chain_config, wallet_address) = config_blockchain()
+    if Path::new("./data/storage/chain_data.json").exists()
+        && !input
+            .confirm("A chain already exists in data/storage, replace it?")
+            .map_err(|err| NodeError::InvalidConfigInput(format!("{}", err)))?
+    {
+        return Err(NodeError::InvalidConfigInput(String::from(
+            "Kept the existing chain",
+        )));
+    }
+
+    let (chain_config, wallet_address) = config_blockchain(input)
+        .map_err(|err_msg| NodeError::InvalidConfigInput(err_msg.to_string()))?;
+
+    let team_allocations = read_team_allocations(&chain_config, input)
         .map_err(|err_msg| NodeError::InvalidConfigInput(err_msg.to_string()))?;
 
     let first_block = Block::genesis_block(
         chain_config.initial_block_reward,
         wallet_address,
         current_timestamp(),
+        team_allocations,
     );
     let chain = vec![first_block];
 
@@ -290,34 +1148,44 @@ pub fn create_new_blockchain() -> Result<(), NodeError> {
     Ok(())
 }
 
-fn config_blockchain() -> Result<(ChainConfig, Address), &'static str> {
-    println!("Name:");
-    let name = read_input("Invalid name")?.trim().to_lowercase();
+fn config_blockchain(input: &Input) -> Result<(ChainConfig, Address), String> {
+    let name = read_input(input, "name", "Name:")?.trim().to_lowercase();
 
-    println!("Chain id:");
-    let chain_id = read_input("Invalid chain id")?
+    let chain_id = read_input(input, "chain-id", "Chain id:")?
         .trim()
         .parse::<u8>()
         .map_err(|_| "Invalid chain id")?;
 
-    println!("Seconds between blocks:");
-    let seconds_between_blocks = read_input("Invalid seconds between blocks")?
+    let seconds_between_blocks = read_input(input, "block-time", "Seconds between blocks:")?
         .trim()
         .parse::<u8>()
         .map_err(|_| "Invalid seconds between blocks")?;
 
-    println!("Initial block reward:");
-    let initial_block_reward = read_input("Invalid initial block reward")?;
-    let initial_block_reward = parse_u256(&initial_block_reward)?;
+    let decimals = match input
+        .optional_text(
+            "decimals",
+            &format!("Decimals (default {DEFAULT_DECIMALS}):"),
+        )
+        .map_err(|err| format!("{}", err))?
+        .trim()
+    {
+        "" => DEFAULT_DECIMALS,
+        decimals => decimals.parse::<u8>().map_err(|_| "Invalid decimals")?,
+    };
+    if decimals > MAX_DECIMALS {
+        return Err(format!("Decimals can't be more than {MAX_DECIMALS}"));
+    }
 
-    println!("Months between halvings:");
-    let months_between_halvings = read_input("Invalid months between halving")?
+    let initial_block_reward = read_input(input, "reward", "Initial block reward:")?;
+    let initial_block_reward = parse_amount(&initial_block_reward, decimals)?;
+
+    let months_between_halvings = read_input(input, "halving-months", "Months between halvings:")?
         .trim()
         .parse::<i64>()
         .map_err(|_| "Invalid months between halving")?;
     let months_between_halvings = months_to_milliseconds(months_between_halvings) as u64;
 
-    let addr = create_wallet().unwrap();
+    let addr = create_wallet(KeyType::Secp256k1, input).map_err(|err| format!("{}", err))?;
     let creation_timestamp = current_timestamp();
 
     let chain_config = ChainConfig {
@@ -327,6 +1195,7 @@ fn config_blockchain() -> Result<(ChainConfig, Address), &'static str> {
         creation_timestamp,
         seconds_between_blocks,
         months_between_halvings,
+        decimals,
     };
 
     let content =
@@ -337,21 +1206,50 @@ fn config_blockchain() -> Result<(ChainConfig, Address), &'static str> {
     Ok((chain_config, addr))
 }
 
-fn read_input(error_message: &str) -> Result<String, &'static str> {
-    let mut buf = String::new();
-    stdin()
-        .read_line(&mut buf)
+fn read_team_allocations(
+    chain_config: &ChainConfig,
+    input: &Input,
+) -> Result<Vec<Transaction>, &'static str> {
+    let creation_timestamp = chain_config.creation_timestamp;
+    let lines = input
+        .lines("allocation", "Vested team allocations, one per line as <address> <amount> <cliff months> <vesting months> (empty line to finish):")
         .map_err(|_| "stdin: Failed to read input")?;
-    Ok(buf)
-}
+    let mut allocations = vec![];
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [receiver, amount, cliff_months, vesting_months] = fields[..] else {
+            return Err("Invalid team allocation");
+        };
+
+        let receiver = receiver
+            .parse::<Address>()
+            .map_err(|_| "Invalid team allocation address")?;
+        let amount = parse_amount(amount, chain_config.decimals)?;
+        let cliff_months = cliff_months
+            .parse::<i64>()
+            .map_err(|_| "Invalid team allocation cliff")?;
+        let vesting_months = vesting_months
+            .parse::<i64>()
+            .map_err(|_| "Invalid team allocation vesting")?;
 
-fn parse_u256(input: &str) -> Result<U256, &'static str> {
-    let mut target = [0u8; 32];
-    let source = input.as_bytes();
-    let bytes_to_copy = source.len().min(32);
+        let schedule = LockSchedule::Vesting {
+            start: creation_timestamp,
+            cliff: creation_timestamp + months_to_milliseconds(cliff_months) as u64,
+            end: creation_timestamp + months_to_milliseconds(vesting_months) as u64,
+        };
+        if !schedule.is_valid() {
+            return Err("Team allocation cliff is longer than its vesting");
+        }
+        allocations.push(
+            Transaction::genesis_tx(amount, receiver, creation_timestamp)
+                .with_kind(TransactionKind::LockedTransfer(schedule)),
+        );
+    }
+    Ok(allocations)
+}
 
-    target[..bytes_to_copy].copy_from_slice(&source[..bytes_to_copy]);
-    Ok(U256::from(&target))
+fn read_input(input: &Input, key: &str, message: &str) -> Result<String, String> {
+    input.text(key, message).map_err(|err| format!("{}", err))
 }
 
 pub async fn sync_node(boot_node_addr: String) -> Result<(), NodeError> {
@@ -392,3 +1290,60 @@ pub async fn run_node(port: String, memory: Mutex<NodeMemory>) -> Result<(), Nod
         .map_err(|err| NodeError::NetworkError(err.to_string()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, Signature, U256};
+
+    use crate::{
+        signature::verification::SignatureScheme,
+        transaction::core::Transaction,
+        utils::ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE},
+    };
+
+    use super::{parse_grpc_transaction_request, signature_to_grpc, transaction_to_grpc};
+
+    // Ids of the transaction as sent, and with its unused signature replaced
+    fn ids_with_swapped_signature(tx: &Transaction) -> (String, String) {
+        let sent = transaction_to_grpc(tx);
+        let mut relayed = sent.clone();
+        relayed.signature = Some(signature_to_grpc(&Signature {
+            r: U256::one(),
+            s: U256::one(),
+            v: 27,
+        }));
+        (
+            parse_grpc_transaction_request(sent).unwrap().id(),
+            parse_grpc_transaction_request(relayed).unwrap().id(),
+        )
+    }
+
+    fn transfer() -> Transaction {
+        Transaction::new(
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            U256::from(10),
+            U256::from(1),
+            EMPTY_SIGNATURE(),
+            0,
+            ADDRESS_ZERO(),
+        )
+    }
+
+    #[test]
+    fn relayers_cannot_change_the_id_through_unused_signatures() {
+        let ed25519 = transfer().with_scheme(SignatureScheme::Ed25519 {
+            public_key: vec![1; 32],
+            signature: vec![2; 64],
+        });
+        let (sent, relayed) = ids_with_swapped_signature(&ed25519);
+        assert_eq!(sent, relayed);
+
+        let multisig = transfer().with_cosignatures(vec![EMPTY_SIGNATURE()]);
+        let (sent, relayed) = ids_with_swapped_signature(&multisig);
+        assert_eq!(sent, relayed);
+
+        let (sent, relayed) = ids_with_swapped_signature(&transfer());
+        assert_ne!(sent, relayed);
+    }
+}
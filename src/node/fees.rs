@@ -0,0 +1,47 @@
+use ethers::types::U256;
+
+use crate::block::core::Block;
+
+// How many of the latest blocks the estimation looks at
+pub const FEE_HISTORY_BLOCKS: usize = 20;
+// Confirmation windows estimated when the client doesn't ask for any
+pub const DEFAULT_FEE_TARGETS: [u32; 3] = [1, 3, 10];
+
+/// Suggests a fee for a transaction to be included within `target_blocks` blocks.
+///
+/// Two bounds are combined and the highest one wins:
+/// - the mempool: the fee that gets ahead of everything that won't fit in the target
+///   window, using the average block size of `recent_blocks` as capacity
+/// - the history: a percentile of the cheapest fee accepted by each recent block, higher
+///   for short windows and lower for long ones
+pub fn estimate_fee(recent_blocks: &[Block], mempool_fees: &[U256], target_blocks: u32) -> U256 {
+    let target_blocks = target_blocks.max(1) as usize;
+
+    let tx_count: usize = recent_blocks
+        .iter()
+        .map(|block| block.transactions().len())
+        .sum();
+    let capacity = (tx_count / recent_blocks.len().max(1)).max(1);
+
+    let mut pending = mempool_fees.to_vec();
+    pending.sort_by(|a, b| b.cmp(a));
+    let mempool_fee = match pending.get(capacity * target_blocks) {
+        Some(fee) => fee.saturating_add(U256::from(1)),
+        None => U256::from(0),
+    };
+
+    let mut accepted: Vec<U256> = recent_blocks
+        .iter()
+        .filter_map(|block| block.transactions().iter().map(|tx| *tx.fee_amount()).min())
+        .collect();
+    accepted.sort();
+    let history_fee = match accepted.len() {
+        0 => U256::from(0),
+        len => {
+            let percentile = 100usize.saturating_sub(10 * target_blocks).clamp(10, 90);
+            accepted[(len - 1) * percentile / 100]
+        }
+    };
+
+    mempool_fee.max(history_fee)
+}
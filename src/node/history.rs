@@ -0,0 +1,45 @@
+use ethers::types::Address;
+
+use crate::{
+    block::core::Block,
+    node::memory::NodeMemory,
+    transaction::{core::Transaction, receipt::TransactionStatus},
+};
+
+/// Transactions involving any of `addresses`, oldest first: the ones included in
+/// `chain` with their location, then the `pending` ones. Receivers of the settlements
+/// recorded in `memory` are involved too.
+pub fn address_transactions(
+    chain: &[Block],
+    pending: &[Transaction],
+    memory: &NodeMemory,
+    addresses: &[Address],
+) -> Vec<(Transaction, TransactionStatus)> {
+    let involved = |tx: &Transaction| {
+        tx.parties().iter().any(|party| addresses.contains(party))
+            || memory
+                .settlements(&tx.id())
+                .iter()
+                .any(|(from, to, _)| addresses.contains(from) || addresses.contains(to))
+    };
+
+    let mut found = vec![];
+    for block in chain.iter() {
+        for (index, tx) in block.transactions().iter().enumerate() {
+            if involved(tx) {
+                found.push((
+                    tx.clone(),
+                    TransactionStatus::Included {
+                        block_hash: block.hash().clone(),
+                        height: *block.height(),
+                        index: index as u64,
+                    },
+                ));
+            }
+        }
+    }
+    for tx in pending.iter().filter(|tx| involved(tx)) {
+        found.push((tx.clone(), TransactionStatus::Pending));
+    }
+    found
+}
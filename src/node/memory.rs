@@ -1,30 +1,81 @@
-use ethers::types::{Address, U256};
-use std::collections::HashMap;
+use ethers::types::{Address, H256, U256};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
 use std::ops::Add;
 use std::sync::Mutex;
 
+use crate::asset::core::{derive_asset_id, AssetId, TokenInfo, NATIVE_ASSET};
 use crate::block::core::Block;
-use crate::transaction::core::Transaction;
+use crate::signature::batch::{verify_batch, VerifiedCache};
+use crate::signature::multisig::MultisigAccount;
+use crate::signature::verification::SignatureError;
+use crate::transaction::core::{Transaction, TransactionKind};
+use crate::transaction::htlc::{derive_htlc_id, Htlc};
+use crate::transaction::lock::TimeLock;
+use crate::transaction::receipt::TransactionStatus;
+use crate::utils::ethers_empty_types::ADDRESS_ZERO;
+use crate::utils::timestamp::current_timestamp;
+use crate::vm::core::{
+    derive_contract_address, execute, CallContext, Host, VmError, MAX_CODE_SIZE, MAX_GAS_LIMIT,
+};
+
+// Pending transactions older than this are dropped from the mempool
+const MEMPOOL_EXPIRY: u64 = 60 * 60 * 1000;
+// Failure receipts kept, the oldest ones are forgotten first
+const FAILED_RECEIPTS_LIMIT: usize = 10_000;
+
+/// Native coin moved from one address to another, as (from, to, amount).
+pub type Movement = (Address, Address, U256);
 
 #[derive(Debug)]
 pub enum NodeMemoryError {
     CacheError(String),
+    TransactionError(String),
+}
+
+impl fmt::Display for NodeMemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeMemoryError::CacheError(msg) | NodeMemoryError::TransactionError(msg) => {
+                write!(f, "{msg}")
+            }
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct NodeMemory {
     cache: Mutex<NodeCache>,
     mempool: Mutex<Vec<Transaction>>,
+    receipts: Mutex<HashMap<String, TransactionStatus>>,
+    // Ids with a failure receipt, oldest first
+    failures: Mutex<VecDeque<String>>,
+    // Native coin moved by executing a transaction rather than listed in it: HTLC
+    // payouts and contract transfers
+    settlements: Mutex<HashMap<String, Vec<Movement>>>,
+    verified: VerifiedCache,
     node_address: Mutex<String>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 struct NodeCache {
-    balances: HashMap<Address, U256>,
+    balances: HashMap<(AssetId, Address), U256>,
+    assets: HashMap<AssetId, TokenInfo>,
     nonces: HashMap<Address, U256>,
+    // Keyed by (owner, spender)
+    allowances: HashMap<(Address, Address), U256>,
+    multisigs: HashMap<Address, MultisigAccount>,
+    // Native balances above include locked funds, these record what is still held back
+    locks: HashMap<Address, Vec<TimeLock>>,
+    contracts: HashMap<Address, Vec<u8>>,
+    // HTLCs that were neither claimed nor refunded yet
+    htlcs: HashMap<H256, Htlc>,
+    storage: HashMap<(Address, U256), U256>,
     block_difficulty: U256,
     block_height: U256,
     block_reward: U256,
+    // Timestamp of the block the state was last advanced to
+    block_timestamp: u64,
 }
 
 impl NodeMemory {
@@ -33,18 +84,61 @@ impl NodeMemory {
         NodeMemory {
             cache: Mutex::new(cache),
             mempool: Mutex::new(vec![]),
+            receipts: Mutex::new(HashMap::new()),
+            failures: Mutex::new(VecDeque::new()),
+            settlements: Mutex::new(HashMap::new()),
+            verified: VerifiedCache::default(),
             node_address: Mutex::new(String::new()),
         }
     }
 
     pub fn balance_of(&self, addr: &Address) -> U256 {
+        self.asset_balance_of(&NATIVE_ASSET, addr)
+    }
+
+    pub fn asset_balance_of(&self, asset_id: &AssetId, addr: &Address) -> U256 {
         let binding = U256::from(0);
-        match self.cache.lock().unwrap().balances.get(addr) {
-            Some(n) => n.clone(),
+        match self.cache.lock().unwrap().balances.get(&(*asset_id, *addr)) {
+            Some(n) => *n,
             None => binding,
         }
     }
 
+    pub fn allowance(&self, owner: &Address, spender: &Address) -> U256 {
+        let binding = U256::from(0);
+        match self
+            .cache
+            .lock()
+            .unwrap()
+            .allowances
+            .get(&(*owner, *spender))
+        {
+            Some(n) => *n,
+            None => binding,
+        }
+    }
+
+    pub fn set_allowance(&mut self, owner: &Address, spender: &Address, amount: &U256) {
+        let _ = &mut self
+            .cache
+            .lock()
+            .unwrap()
+            .allowances
+            .insert((*owner, *spender), *amount);
+    }
+
+    pub fn htlc(&self, id: &H256) -> Option<Htlc> {
+        self.cache.lock().unwrap().htlcs.get(id).cloned()
+    }
+
+    pub fn contract_code(&self, addr: &Address) -> Option<Vec<u8>> {
+        self.cache.lock().unwrap().contracts.get(addr).cloned()
+    }
+
+    pub fn token(&self, asset_id: &AssetId) -> Option<TokenInfo> {
+        self.cache.lock().unwrap().assets.get(asset_id).cloned()
+    }
+
     pub fn current_nonce(&self, addr: &Address) -> U256 {
         let binding = U256::from(0);
         match self.cache.lock().unwrap().nonces.get(addr) {
@@ -53,24 +147,214 @@ impl NodeMemory {
         }
     }
 
-    pub fn block_difficulty(&self) -> U256 {
-        self.cache.lock().unwrap().block_difficulty.clone()
+    pub fn locked_balance_of(&self, addr: &Address, timestamp: u64) -> U256 {
+        let height = self.block_height();
+        match self.cache.lock().unwrap().locks.get(addr) {
+            Some(locks) => locks.iter().fold(U256::from(0), |acc, lock| {
+                acc.saturating_add(lock.locked_amount(timestamp, &height))
+            }),
+            None => U256::from(0),
+        }
+    }
+
+    /// Balance the address can spend at the given time, excluding locked funds.
+    pub fn spendable_balance_of(&self, addr: &Address, timestamp: u64) -> U256 {
+        self.balance_of(addr)
+            .saturating_sub(self.locked_balance_of(addr, timestamp))
+    }
+
+    pub fn multisig(&self, addr: &Address) -> Option<MultisigAccount> {
+        self.cache.lock().unwrap().multisigs.get(addr).cloned()
     }
 
     pub fn block_height(&self) -> U256 {
         self.cache.lock().unwrap().block_height.clone()
     }
 
-    pub fn block_reward(&self) -> U256 {
-        self.cache.lock().unwrap().block_reward.clone()
+    pub fn block_timestamp(&self) -> u64 {
+        self.cache.lock().unwrap().block_timestamp
     }
 
     pub fn set_balance(&mut self, addr: &Address, amount: &U256) {
-        let _ = &mut self.cache.lock().unwrap().balances.insert(*addr, *amount);
+        self.set_asset_balance(&NATIVE_ASSET, addr, amount);
+    }
+
+    pub fn set_asset_balance(&mut self, asset_id: &AssetId, addr: &Address, amount: &U256) {
+        let _ = &mut self
+            .cache
+            .lock()
+            .unwrap()
+            .balances
+            .insert((*asset_id, *addr), *amount);
+    }
+
+    pub fn set_token(&mut self, asset_id: &AssetId, token: TokenInfo) {
+        let _ = &mut self.cache.lock().unwrap().assets.insert(*asset_id, token);
+    }
+
+    pub fn register_multisig(&mut self, addr: &Address, account: &MultisigAccount) {
+        let _ = &mut self
+            .cache
+            .lock()
+            .unwrap()
+            .multisigs
+            .insert(*addr, account.clone());
+    }
+
+    pub fn add_time_lock(&mut self, addr: &Address, lock: TimeLock) {
+        self.cache
+            .lock()
+            .unwrap()
+            .locks
+            .entry(*addr)
+            .or_default()
+            .push(lock);
     }
 
     pub fn push_to_mempool(&mut self, tx: &Transaction) {
         let _ = &mut self.mempool.lock().unwrap().push(tx.clone());
+        self.set_transaction_status(&tx.id(), TransactionStatus::Pending);
+    }
+
+    pub fn mempool_fees(&self) -> Vec<U256> {
+        self.mempool
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|tx| *tx.fee_amount())
+            .collect()
+    }
+
+    pub fn mempool_transactions(&self) -> Vec<Transaction> {
+        self.mempool.lock().unwrap().clone()
+    }
+
+    pub fn mempool_transaction(&self, tx_id: &str) -> Option<Transaction> {
+        self.mempool
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|tx| tx.id() == tx_id)
+            .cloned()
+    }
+
+    pub fn transaction_status(&self, tx_id: &str) -> Option<TransactionStatus> {
+        self.receipts.lock().unwrap().get(tx_id).cloned()
+    }
+
+    pub fn set_transaction_status(&mut self, tx_id: &str, status: TransactionStatus) {
+        let _ = &mut self
+            .receipts
+            .lock()
+            .unwrap()
+            .insert(tx_id.to_string(), status);
+    }
+
+    pub fn settlements(&self, tx_id: &str) -> Vec<Movement> {
+        self.settlements
+            .lock()
+            .unwrap()
+            .get(tx_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn add_settlement(&mut self, tx_id: &str, from: &Address, to: &Address, amount: &U256) {
+        self.settlements
+            .lock()
+            .unwrap()
+            .entry(tx_id.to_string())
+            .or_default()
+            .push((*from, *to, *amount));
+    }
+
+    /// Records a rejected transaction, without overwriting the receipt of one the node
+    /// already knows about (e.g. a replay of an included transaction). Only the last
+    /// `FAILED_RECEIPTS_LIMIT` failures are kept.
+    pub fn record_failure(&mut self, tx_id: &str, reason: String) {
+        let mut receipts = self.receipts.lock().unwrap();
+        if receipts.contains_key(tx_id) {
+            return;
+        }
+        receipts.insert(tx_id.to_string(), TransactionStatus::Failed(reason));
+
+        let mut failures = self.failures.lock().unwrap();
+        failures.push_back(tx_id.to_string());
+        while failures.len() > FAILED_RECEIPTS_LIMIT {
+            let oldest = failures.pop_front().unwrap();
+            // The transaction may have gone through since it failed
+            if matches!(receipts.get(&oldest), Some(TransactionStatus::Failed(_))) {
+                receipts.remove(&oldest);
+            }
+        }
+    }
+
+    /// Advances the chain info to the block, marks its transactions as included, takes
+    /// them out of the mempool and drops whatever pending transaction has expired. The
+    /// state isn't touched, see `rebase`.
+    pub fn include_block(&mut self, block: &Block) {
+        self.update_block_info(block);
+        let mut included = vec![];
+        for (index, tx) in block.transactions().iter().enumerate() {
+            let tx_id = tx.id();
+            self.set_transaction_status(
+                &tx_id,
+                TransactionStatus::Included {
+                    block_hash: block.hash().clone(),
+                    height: *block.height(),
+                    index: index as u64,
+                },
+            );
+            included.push(tx_id);
+        }
+
+        let now = current_timestamp();
+        let mut dropped = vec![];
+        self.mempool.lock().unwrap().retain(|tx| {
+            let tx_id = tx.id();
+            if included.contains(&tx_id) {
+                false
+            } else if tx.timestamp() + MEMPOOL_EXPIRY < now {
+                dropped.push(tx_id);
+                false
+            } else {
+                true
+            }
+        });
+        for tx_id in dropped.iter() {
+            self.set_transaction_status(tx_id, TransactionStatus::Dropped);
+        }
+    }
+
+    /// Takes the state of `confirmed`, the chain after its latest block, and applies the
+    /// transactions still in the mempool on top of it. Those that no longer apply, e.g.
+    /// because a block spent the same nonce, leave the mempool with a failure receipt.
+    // Call after `include_block`, so included and expired transactions are gone and
+    // nothing they changed survives in the pending state
+    pub fn rebase(&mut self, confirmed: NodeMemory) {
+        *self.cache.lock().unwrap() = confirmed.cache.into_inner().unwrap();
+        *self.settlements.lock().unwrap() = confirmed.settlements.into_inner().unwrap();
+
+        let pending = std::mem::take(&mut *self.mempool.lock().unwrap());
+        for tx in pending {
+            let from = tx.from();
+            let res =
+                verify_batch(&tx.signature_checks(&self.current_nonce(from), self.multisig(from)))
+                    .map_err(|err| format!("Invalid signature: {}", err))
+                    .and_then(|_| {
+                        self.apply_transaction(&ADDRESS_ZERO(), &tx, tx.timestamp())
+                            .map_err(|err| format!("{}", err))
+                    });
+            match res {
+                Ok(_) => self.mempool.lock().unwrap().push(tx),
+                Err(reason) => {
+                    let tx_id = tx.id();
+                    // Replaces the pending receipt
+                    self.receipts.lock().unwrap().remove(&tx_id);
+                    self.record_failure(&tx_id, reason);
+                }
+            }
+        }
     }
 
     pub fn increment_nonce(&mut self, addr: &Address) {
@@ -84,23 +368,24 @@ impl NodeMemory {
     }
 
     pub fn set_block_height(&mut self, height: &U256) {
-        let mut block_height = self.cache.lock().unwrap().block_height;
-        block_height = *height;
+        self.cache.lock().unwrap().block_height = *height;
     }
 
     pub fn set_block_reward(&mut self, reward: &U256) {
-        let mut block_reward = self.cache.lock().unwrap().block_reward;
-        block_reward = *reward;
+        self.cache.lock().unwrap().block_reward = *reward;
     }
 
     pub fn set_block_difficulty(&mut self, difficulty: &U256) {
-        let mut block_difficulty = self.cache.lock().unwrap().block_difficulty;
-        block_difficulty = *difficulty;
+        self.cache.lock().unwrap().block_difficulty = *difficulty;
+    }
+
+    pub fn set_block_timestamp(&mut self, timestamp: u64) {
+        self.cache.lock().unwrap().block_timestamp = timestamp;
     }
 
     pub fn increment_block_height(&mut self) {
-        let mut block_height = self.cache.lock().unwrap().block_height;
-        block_height = block_height.add(1);
+        let block_height = self.block_height();
+        self.set_block_height(&block_height.add(1));
     }
 
     pub fn node_address(&self) -> String {
@@ -112,71 +397,859 @@ impl NodeMemory {
         *node_address = addr;
     }
 
+    /// Copy of the chain state, used to try transactions out without touching the node.
+    pub fn snapshot(&self) -> NodeMemory {
+        let cache = self.cache.lock().unwrap().clone();
+        NodeMemory {
+            cache: Mutex::new(cache),
+            ..NodeMemory::new()
+        }
+    }
+
     pub fn cache(chain: &str) -> Result<NodeMemory, NodeMemoryError> {
         println!("Caching memory...");
         let chain = serde_json::from_str::<Vec<Block>>(&chain)
             .map_err(|_| NodeMemoryError::CacheError(String::from("Could not read chain data")))?;
+        for block in chain.iter() {
+            println!("{:#?}", block);
+        }
 
-        let zero_address = Address::from([0u8; 20]);
+        NodeMemory::rebuild(&chain, true)
+    }
+
+    /// Builds the state confirmed by the given blocks, trusting their signatures.
+    pub fn replay(chain: &[Block]) -> Result<NodeMemory, NodeMemoryError> {
+        NodeMemory::rebuild(chain, false)
+    }
+
+    fn rebuild(chain: &[Block], verify_signatures: bool) -> Result<NodeMemory, NodeMemoryError> {
         let mut node_memory = NodeMemory::new();
-        let len = chain.len();
 
-        for (i, block) in chain.iter().enumerate() {
-            println!("{:#?}", block);
-            if i == len - 1 {
-                node_memory.update_last_block_info(block);
+        for block in chain.iter() {
+            if verify_signatures {
+                node_memory
+                    .verify_block_signatures(block, &node_memory.verified)
+                    .map_err(|err| {
+                        NodeMemoryError::CacheError(format!(
+                            "Invalid signature in block {}: {:?}",
+                            block.height(),
+                            err
+                        ))
+                    })?;
             }
-
-            node_memory.process_transactions(zero_address, block.transactions());
+            node_memory.process_block(block)?;
         }
 
         Ok(node_memory)
     }
 
-    fn update_last_block_info(&mut self, block: &Block) {
+    /// Applies a block on top of this state and marks its transactions included. An
+    /// invalid transaction leaves the state partly applied, so blocks that weren't
+    /// checked yet are applied to a copy.
+    pub fn process_block(&mut self, block: &Block) -> Result<(), NodeMemoryError> {
+        // Height locks are checked against the block being applied
+        self.update_block_info(block);
+        self.process_transactions(ADDRESS_ZERO(), block.transactions())?;
+        self.include_block(block);
+        Ok(())
+    }
+
+    pub fn verified_cache(&self) -> &VerifiedCache {
+        &self.verified
+    }
+
+    /// Checks the signatures of a block's transactions on top of this state in one
+    /// parallel batch. Transactions found in `verified` at the same nonce are skipped
+    /// and dropped from it.
+    pub fn verify_block_signatures(
+        &self,
+        block: &Block,
+        verified: &VerifiedCache,
+    ) -> Result<(), SignatureError> {
+        let mut nonces: HashMap<Address, U256> = HashMap::new();
+        // Accounts created earlier in the same block
+        let mut multisigs: HashMap<Address, MultisigAccount> = HashMap::new();
+        let mut checks = vec![];
+        let mut cached = vec![];
+
+        for tx in block.transactions().iter() {
+            let from = tx.from();
+            // Only the genesis allocations come unsigned from the zero address, anywhere
+            // else they would mint coins out of nothing
+            if from.is_zero() {
+                if block.height().is_zero() {
+                    continue;
+                }
+                return Err(SignatureError::UnsignedTransaction);
+            }
+            let nonce = *nonces
+                .entry(*from)
+                .or_insert_with(|| self.current_nonce(from));
+            nonces.insert(*from, nonce + 1);
+
+            let multisig = multisigs.get(from).cloned().or_else(|| self.multisig(from));
+            if let TransactionKind::CreateMultisig(account) = tx.kind() {
+                multisigs.insert(
+                    MultisigAccount::derive_address(from, &nonce),
+                    account.clone(),
+                );
+            }
+
+            let tx_id = tx.id();
+            if verified.contains(&tx_id, &nonce) {
+                cached.push((tx_id, nonce));
+            } else {
+                checks.extend(tx.signature_checks(&nonce, multisig));
+            }
+        }
+
+        verify_batch(&checks)?;
+        for (tx_id, nonce) in cached.iter() {
+            verified.remove(tx_id, nonce);
+        }
+        Ok(())
+    }
+
+    fn update_block_info(&mut self, block: &Block) {
         self.set_block_height(block.height());
         self.set_block_difficulty(block.difficulty());
         self.set_block_reward(block.reward());
+        self.set_block_timestamp(block.header().timestamp);
     }
 
-    fn process_transactions(&mut self, zero_address: Address, transactions: &[Transaction]) {
+    // Block transactions are checked at the block's time, so every node reaches the
+    // same result whatever its clock says
+    fn process_transactions(
+        &mut self,
+        zero_address: Address,
+        transactions: &[Transaction],
+    ) -> Result<(), NodeMemoryError> {
+        let now = self.block_timestamp();
         for tx in transactions.iter() {
-            self.process_sender(&zero_address, tx);
-            self.process_receiver(tx);
-            self.process_fee(&zero_address, tx);
+            if *tx.from() == zero_address && !self.block_height().is_zero() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Only the genesis block can mint",
+                )));
+            }
+            self.apply_transaction(&zero_address, tx, now)?;
         }
+        Ok(())
     }
 
-    fn process_sender(&mut self, zero_address: &Address, tx: &Transaction) {
+    /// Applies a transaction to the cached state. The sender is checked against the
+    /// total of all outputs and the fee (or the sponsor against the fee) before anything
+    /// is written, so a batch transfer is either applied entirely or not at all. Locks
+    /// and HTLC timeouts are checked at `now`.
+    pub fn apply_transaction(
+        &mut self,
+        zero_address: &Address,
+        tx: &Transaction,
+        now: u64,
+    ) -> Result<(), NodeMemoryError> {
+        let cost = tx
+            .total_cost()
+            .ok_or(NodeMemoryError::TransactionError(String::from(
+                "Transaction amount overflow",
+            )))?;
+        if *tx.from() != *zero_address && self.spendable_balance_of(tx.from(), now) < cost {
+            return Err(NodeMemoryError::TransactionError(String::from(
+                "Insufficient balance",
+            )));
+        }
+
+        if let Some(sponsorship) = tx.sponsorship() {
+            if sponsorship.sponsor() == tx.from() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Sender cannot sponsor its own transaction",
+                )));
+            }
+            if self.spendable_balance_of(sponsorship.sponsor(), now) < *tx.fee_amount() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Insufficient sponsor balance",
+                )));
+            }
+        }
+
+        let multisig = match tx.kind() {
+            TransactionKind::CreateMultisig(account) => {
+                let addr =
+                    MultisigAccount::derive_address(tx.from(), &self.current_nonce(tx.from()));
+                if !account.is_valid() || self.multisig(&addr).is_some() {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Invalid multisig account",
+                    )));
+                }
+                Some((addr, account))
+            }
+            TransactionKind::LockedTransfer(schedule) if !schedule.is_valid() => {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Invalid lock schedule",
+                )));
+            }
+            _ => None,
+        };
+        let token_issue = self.check_token_operation(tx)?;
+        let deployment = self.check_contract_operation(tx)?;
+        let htlc = self.check_htlc_operation(tx, now)?;
+        if let TransactionKind::TransferFrom(owner) = tx.kind() {
+            // The fee and the amount would both come out of the same balance
+            if owner == tx.from() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Owner cannot spend from its own allowance",
+                )));
+            }
+            if self.allowance(owner, tx.from()) < *tx.amount() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Allowance exceeded",
+                )));
+            }
+            if self.spendable_balance_of(owner, now) < *tx.amount() {
+                return Err(NodeMemoryError::TransactionError(String::from(
+                    "Insufficient owner balance",
+                )));
+            }
+        }
+
+        // The fee is charged and the nonce used even when the call fails, only the
+        // changes made by the call itself are thrown away
+        if let TransactionKind::CallContract { input, gas_limit } = tx.kind() {
+            let fee = match tx.sponsorship() {
+                Some(_) => U256::zero(),
+                None => *tx.fee_amount(),
+            };
+            self.process_sender(zero_address, tx, &fee);
+            self.process_fee(zero_address, tx);
+
+            let ctx = CallContext {
+                contract: *tx.to(),
+                caller: *tx.from(),
+                value: *tx.amount(),
+                input,
+                gas_limit: *gas_limit,
+            };
+            let code = self.contract_code(tx.to()).unwrap();
+            let mut journal = CallJournal::new(self);
+            let result = match journal.transfer(tx.from(), tx.to(), tx.amount()) {
+                true => execute(&mut journal, &code, &ctx),
+                false => Err(VmError::InsufficientBalance),
+            };
+            match result {
+                Ok(result) => {
+                    // Kept off stdout, which carries the CLI's tables and CSV output
+                    eprintln!(
+                        "Contract call used {} gas and returned {:?}",
+                        result.gas_used, result.output
+                    );
+                    let CallJournal {
+                        storage,
+                        balances,
+                        transfers,
+                        ..
+                    } = journal;
+                    for ((contract, key), value) in storage.iter() {
+                        self.cache
+                            .lock()
+                            .unwrap()
+                            .storage
+                            .insert((*contract, *key), *value);
+                    }
+                    for (addr, balance) in balances.iter() {
+                        self.set_balance(addr, balance);
+                    }
+                    // The first transfer is the call value, already one of the outputs
+                    let tx_id = tx.id();
+                    for (from, to, amount) in transfers.iter().skip(1) {
+                        self.add_settlement(&tx_id, from, to, amount);
+                    }
+                }
+                Err(err) => eprintln!("Contract call failed, only the fee was charged: {}", err),
+            }
+            return Ok(());
+        }
+
+        self.process_sender(zero_address, tx, &cost);
+        self.process_receivers(tx);
+        self.process_fee(zero_address, tx);
+        if let TransactionKind::LockedTransfer(schedule) = tx.kind() {
+            self.add_time_lock(tx.to(), TimeLock::new(*tx.amount(), schedule.clone()));
+        }
+        if let Some((addr, account)) = multisig {
+            self.register_multisig(&addr, account);
+        }
+        self.process_token_operation(tx, token_issue);
+        self.process_allowance(tx);
+        self.process_htlc(tx, htlc);
+        if let (TransactionKind::DeployContract(code), Some(addr)) = (tx.kind(), deployment) {
+            eprintln!("Deployed contract: {:?}", addr);
+            self.cache
+                .lock()
+                .unwrap()
+                .contracts
+                .insert(addr, code.clone());
+        }
+        Ok(())
+    }
+
+    // Validates deploys and calls, returning the address a deploy creates
+    fn check_contract_operation(
+        &self,
+        tx: &Transaction,
+    ) -> Result<Option<Address>, NodeMemoryError> {
+        match tx.kind() {
+            TransactionKind::DeployContract(code) => {
+                let addr = derive_contract_address(tx.from(), &self.current_nonce(tx.from()));
+                if code.is_empty()
+                    || code.len() > MAX_CODE_SIZE
+                    || self.contract_code(&addr).is_some()
+                {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Invalid contract deploy",
+                    )));
+                }
+                Ok(Some(addr))
+            }
+            TransactionKind::CallContract { gas_limit, .. } => {
+                if *gas_limit > MAX_GAS_LIMIT {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Gas limit too high",
+                    )));
+                }
+                if self.contract_code(tx.to()).is_none() {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Unknown contract",
+                    )));
+                }
+                Ok(None)
+            }
+            // Contracts only move funds they can spend right away
+            TransactionKind::LockedTransfer(_) if self.contract_code(tx.to()).is_some() => {
+                Err(NodeMemoryError::TransactionError(String::from(
+                    "Contracts can't receive locked funds",
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // Validates an HTLC operation, returning the id of the HTLC it creates if any
+    fn check_htlc_operation(
+        &self,
+        tx: &Transaction,
+        now: u64,
+    ) -> Result<Option<H256>, NodeMemoryError> {
+        let invalid = |reason: &str| Err(NodeMemoryError::TransactionError(String::from(reason)));
+        match tx.kind() {
+            TransactionKind::HtlcLock { timeout, .. } => {
+                if *timeout <= now {
+                    return invalid("HTLC timeout already passed");
+                }
+                Ok(Some(derive_htlc_id(
+                    tx.from(),
+                    &self.current_nonce(tx.from()),
+                )))
+            }
+            TransactionKind::HtlcClaim { id, preimage } => match self.htlc(id) {
+                None => invalid("Unknown HTLC"),
+                Some(htlc) if htlc.is_expired(now) => invalid("HTLC expired"),
+                Some(htlc) if !htlc.matches(preimage) => invalid("Invalid HTLC preimage"),
+                Some(_) => Ok(None),
+            },
+            TransactionKind::HtlcRefund(id) => match self.htlc(id) {
+                None => invalid("Unknown HTLC"),
+                Some(htlc) if !htlc.is_expired(now) => invalid("HTLC not expired"),
+                Some(_) => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn process_htlc(&mut self, tx: &Transaction, created: Option<H256>) {
+        let settled = match tx.kind() {
+            TransactionKind::HtlcLock { hash_lock, timeout } => {
+                let id = created.unwrap();
+                let htlc = Htlc::new(*tx.from(), *tx.to(), *tx.amount(), *hash_lock, *timeout);
+                self.cache.lock().unwrap().htlcs.insert(id, htlc);
+                return;
+            }
+            TransactionKind::HtlcClaim { id, .. } => {
+                let htlc = self.cache.lock().unwrap().htlcs.remove(id).unwrap();
+                (*htlc.recipient(), *htlc.amount())
+            }
+            TransactionKind::HtlcRefund(id) => {
+                let htlc = self.cache.lock().unwrap().htlcs.remove(id).unwrap();
+                (*htlc.sender(), *htlc.amount())
+            }
+            _ => return,
+        };
+        let (receiver, amount) = settled;
+        // Escrowed funds count as held by the zero address until they are settled
+        self.add_settlement(&tx.id(), &ADDRESS_ZERO(), &receiver, &amount);
+        let receiver_balance = self.balance_of(&receiver);
+        self.set_balance(&receiver, &receiver_balance.checked_add(amount).unwrap());
+    }
+
+    // Validates a token operation, returning the id of the asset it issues if any
+    fn check_token_operation(&self, tx: &Transaction) -> Result<Option<AssetId>, NodeMemoryError> {
+        let from = tx.from();
+        match tx.kind() {
+            TransactionKind::IssueToken { metadata, supply } => {
+                let asset_id = derive_asset_id(from, &self.current_nonce(from));
+                if !metadata.is_valid() || supply.is_zero() || self.token(&asset_id).is_some() {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Invalid token issue",
+                    )));
+                }
+                Ok(Some(asset_id))
+            }
+            TransactionKind::TransferToken(asset_id) | TransactionKind::BurnToken(asset_id) => {
+                if self.token(asset_id).is_none() {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Unknown token",
+                    )));
+                }
+                if self.asset_balance_of(asset_id, from) < *tx.amount() {
+                    return Err(NodeMemoryError::TransactionError(String::from(
+                        "Insufficient token balance",
+                    )));
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn process_token_operation(&mut self, tx: &Transaction, token_issue: Option<AssetId>) {
+        let from = tx.from();
         let amount = tx.amount();
+        match tx.kind() {
+            TransactionKind::IssueToken { metadata, supply } => {
+                let asset_id = token_issue.unwrap();
+                self.set_token(&asset_id, TokenInfo::new(*from, metadata.clone(), *supply));
+                self.set_asset_balance(&asset_id, from, supply);
+            }
+            TransactionKind::TransferToken(asset_id) => {
+                let sender_balance = self.asset_balance_of(asset_id, from);
+                self.set_asset_balance(asset_id, from, &(sender_balance - amount));
+
+                let receiver_balance = self.asset_balance_of(asset_id, tx.to());
+                let new_balance = receiver_balance.checked_add(*amount).unwrap();
+                self.set_asset_balance(asset_id, tx.to(), &new_balance);
+            }
+            TransactionKind::BurnToken(asset_id) => {
+                let sender_balance = self.asset_balance_of(asset_id, from);
+                self.set_asset_balance(asset_id, from, &(sender_balance - amount));
+
+                let mut token = self.token(asset_id).unwrap();
+                token.set_supply(*token.supply() - amount);
+                self.set_token(asset_id, token);
+            }
+            _ => {}
+        }
+    }
+
+    fn process_allowance(&mut self, tx: &Transaction) {
+        let amount = tx.amount();
+        match tx.kind() {
+            TransactionKind::Approve(spender) => self.set_allowance(tx.from(), spender, amount),
+            TransactionKind::TransferFrom(owner) => {
+                let allowance = self.allowance(owner, tx.from());
+                self.set_allowance(owner, tx.from(), &(allowance - amount));
+
+                let owner_balance = self.balance_of(owner);
+                self.set_balance(owner, &(owner_balance - amount));
+
+                let receiver_balance = self.balance_of(tx.to());
+                let new_balance = receiver_balance.checked_add(*amount).unwrap();
+                self.set_balance(tx.to(), &new_balance);
+            }
+            _ => {}
+        }
+    }
+
+    fn process_sender(&mut self, zero_address: &Address, tx: &Transaction, cost: &U256) {
         let from = tx.from();
         if *from != *zero_address {
             let sender_balance = self.balance_of(from);
-            let new_balance = sender_balance.checked_sub(*amount).unwrap();
+            let new_balance = sender_balance.checked_sub(*cost).unwrap();
             self.set_balance(from, &new_balance);
             self.increment_nonce(from);
         }
     }
 
-    fn process_receiver(&mut self, tx: &Transaction) {
-        let amount = tx.amount();
-        let to = tx.to();
-        let receiver_balance = self.balance_of(to);
-        let new_balance = receiver_balance.checked_add(*amount).unwrap();
-        self.set_balance(to, &new_balance);
+    fn process_receivers(&mut self, tx: &Transaction) {
+        for output in tx.outputs().iter() {
+            let to = output.to();
+            let receiver_balance = self.balance_of(to);
+            let new_balance = receiver_balance.checked_add(*output.amount()).unwrap();
+            self.set_balance(to, &new_balance);
+        }
     }
 
+    // Without a sponsor the sender already paid the fee in `process_sender`, it is
+    // burned when there is no fee receiver
     fn process_fee(&mut self, zero_address: &Address, tx: &Transaction) {
         let fee_amount = tx.fee_amount();
+        if let Some(sponsorship) = tx.sponsorship() {
+            let sponsor = sponsorship.sponsor();
+            let sponsor_balance = self.balance_of(sponsor);
+            self.set_balance(sponsor, &sponsor_balance.checked_sub(*fee_amount).unwrap());
+        }
         let fee_receiver = tx.fee_receiver();
         if *fee_receiver != *zero_address {
-            let sender_balance = self.balance_of(fee_receiver);
-            let new_sender_balance = sender_balance.checked_sub(*fee_amount).unwrap();
-            self.set_balance(fee_receiver, &new_sender_balance);
-
             let receiver_balance = self.balance_of(fee_receiver);
             let new_receiver_balance = receiver_balance.checked_add(*fee_amount).unwrap();
             self.set_balance(fee_receiver, &new_receiver_balance);
         }
     }
 }
+
+/// Writes of a running contract call, kept apart from the node state until the call
+/// succeeds so a failed one needs no copy of the state to roll back.
+struct CallJournal<'a> {
+    memory: &'a NodeMemory,
+    storage: HashMap<(Address, U256), U256>,
+    balances: HashMap<Address, U256>,
+    // Every transfer made, in order
+    transfers: Vec<Movement>,
+}
+
+impl<'a> CallJournal<'a> {
+    fn new(memory: &'a NodeMemory) -> CallJournal<'a> {
+        CallJournal {
+            memory,
+            storage: HashMap::new(),
+            balances: HashMap::new(),
+            transfers: vec![],
+        }
+    }
+}
+
+impl Host for CallJournal<'_> {
+    fn storage(&self, contract: &Address, key: &U256) -> U256 {
+        match self.storage.get(&(*contract, *key)) {
+            Some(value) => *value,
+            None => match self
+                .memory
+                .cache
+                .lock()
+                .unwrap()
+                .storage
+                .get(&(*contract, *key))
+            {
+                Some(value) => *value,
+                None => U256::from(0),
+            },
+        }
+    }
+
+    fn set_storage(&mut self, contract: &Address, key: &U256, value: &U256) {
+        self.storage.insert((*contract, *key), *value);
+    }
+
+    fn balance(&self, addr: &Address) -> U256 {
+        match self.balances.get(addr) {
+            Some(balance) => *balance,
+            None => self.memory.balance_of(addr),
+        }
+    }
+
+    fn transfer(&mut self, from: &Address, to: &Address, amount: &U256) -> bool {
+        let sender_balance = self.balance(from);
+        if sender_balance < *amount {
+            return false;
+        }
+        self.balances.insert(*from, sender_balance - amount);
+
+        let receiver_balance = self.balance(to);
+        let new_balance = receiver_balance.checked_add(*amount).unwrap();
+        self.balances.insert(*to, new_balance);
+        self.transfers.push((*from, *to, *amount));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Address, U256};
+
+    use crate::{
+        block::core::Block,
+        signature::verification::KeyType,
+        transaction::{
+            core::{Transaction, TransactionKind},
+            lock::LockSchedule,
+            receipt::TransactionStatus,
+        },
+        utils::{
+            ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE},
+            timestamp::current_timestamp,
+        },
+        vm::{
+            core::derive_contract_address,
+            opcode::{PUSH1, REVERT, SSTORE, STOP, TRANSFER},
+        },
+        wallet::core::WalletKey,
+    };
+
+    use super::{NodeMemory, VerifiedCache};
+
+    fn block(height: u64, transactions: Vec<Transaction>) -> Block {
+        Block::new(
+            String::new(),
+            height * 1000,
+            U256::zero(),
+            String::new(),
+            String::new(),
+            U256::one(),
+            U256::from(height),
+            U256::zero(),
+            transactions,
+        )
+    }
+
+    #[test]
+    fn replay_spends_a_height_lock_in_a_later_block() {
+        let owner = Address::from_low_u64_be(1);
+        let receiver = Address::from_low_u64_be(2);
+        let allocation = Transaction::genesis_tx(U256::from(100), owner, 0).with_kind(
+            TransactionKind::LockedTransfer(LockSchedule::UntilHeight(U256::from(2))),
+        );
+        let spend = Transaction::new(
+            owner,
+            receiver,
+            U256::from(60),
+            U256::zero(),
+            EMPTY_SIGNATURE(),
+            2000,
+            ADDRESS_ZERO(),
+        );
+        // The spend isn't in the last block, which used to be the only one replayed
+        // at its own height
+        let chain = vec![
+            block(0, vec![allocation]),
+            block(1, vec![]),
+            block(2, vec![spend]),
+            block(3, vec![]),
+        ];
+
+        let mem = NodeMemory::replay(&chain).unwrap();
+        assert_eq!(mem.balance_of(&receiver), U256::from(60));
+        assert_eq!(mem.block_height(), U256::from(3));
+    }
+
+    #[test]
+    fn failed_call_charges_the_fee_and_uses_the_nonce() {
+        let caller = Address::from_low_u64_be(1);
+        let tx = |amount: u64, fee: u64, kind: TransactionKind| {
+            Transaction::new(
+                caller,
+                derive_contract_address(&caller, &U256::zero()),
+                U256::from(amount),
+                U256::from(fee),
+                EMPTY_SIGNATURE(),
+                0,
+                ADDRESS_ZERO(),
+            )
+            .with_kind(kind)
+        };
+        let chain = vec![
+            block(
+                0,
+                vec![Transaction::genesis_tx(U256::from(1000), caller, 0)],
+            ),
+            block(
+                1,
+                vec![tx(0, 0, TransactionKind::DeployContract(vec![REVERT]))],
+            ),
+            block(
+                2,
+                vec![tx(
+                    100,
+                    10,
+                    TransactionKind::CallContract {
+                        input: vec![],
+                        gas_limit: 10,
+                    },
+                )],
+            ),
+        ];
+
+        let mem = NodeMemory::replay(&chain).unwrap();
+        assert_eq!(mem.balance_of(&caller), U256::from(990));
+        assert_eq!(mem.current_nonce(&caller), U256::from(2));
+    }
+
+    #[test]
+    fn contract_transfers_are_recorded_as_settlements() {
+        let caller = Address::from_low_u64_be(1);
+        let receiver = Address::from_low_u64_be(7);
+        let contract = derive_contract_address(&caller, &U256::zero());
+        let tx = |amount: u64, kind: TransactionKind| {
+            Transaction::new(
+                caller,
+                contract,
+                U256::from(amount),
+                U256::zero(),
+                EMPTY_SIGNATURE(),
+                0,
+                ADDRESS_ZERO(),
+            )
+            .with_kind(kind)
+        };
+        let code = vec![PUSH1, 40, PUSH1, 7, TRANSFER, STOP];
+        let call = tx(
+            100,
+            TransactionKind::CallContract {
+                input: vec![],
+                gas_limit: 200,
+            },
+        );
+        let chain = vec![
+            block(
+                0,
+                vec![Transaction::genesis_tx(U256::from(1000), caller, 0)],
+            ),
+            block(1, vec![tx(0, TransactionKind::DeployContract(code))]),
+            block(2, vec![call.clone()]),
+        ];
+
+        let mem = NodeMemory::replay(&chain).unwrap();
+        assert_eq!(mem.balance_of(&receiver), U256::from(40));
+        // The call value itself is one of the transaction's outputs, not a settlement
+        assert_eq!(
+            mem.settlements(&call.id()),
+            vec![(contract, receiver, U256::from(40))]
+        );
+    }
+
+    #[test]
+    fn reverted_call_leaves_storage_and_balances_untouched() {
+        let caller = Address::from_low_u64_be(1);
+        let receiver = Address::from_low_u64_be(7);
+        let contract = derive_contract_address(&caller, &U256::zero());
+        let tx = |amount: u64, kind: TransactionKind| {
+            Transaction::new(
+                caller,
+                contract,
+                U256::from(amount),
+                U256::zero(),
+                EMPTY_SIGNATURE(),
+                0,
+                ADDRESS_ZERO(),
+            )
+            .with_kind(kind)
+        };
+        let code = vec![
+            PUSH1, 5, PUSH1, 1, SSTORE, PUSH1, 40, PUSH1, 7, TRANSFER, REVERT,
+        ];
+        let call = tx(
+            100,
+            TransactionKind::CallContract {
+                input: vec![],
+                gas_limit: 400,
+            },
+        );
+        let chain = vec![
+            block(
+                0,
+                vec![Transaction::genesis_tx(U256::from(1000), caller, 0)],
+            ),
+            block(1, vec![tx(0, TransactionKind::DeployContract(code))]),
+            block(2, vec![call.clone()]),
+        ];
+
+        let mem = NodeMemory::replay(&chain).unwrap();
+        assert_eq!(mem.balance_of(&caller), U256::from(1000));
+        assert_eq!(mem.balance_of(&contract), U256::zero());
+        assert_eq!(mem.balance_of(&receiver), U256::zero());
+        assert!(mem.cache.lock().unwrap().storage.is_empty());
+        assert!(mem.settlements(&call.id()).is_empty());
+    }
+
+    #[test]
+    fn blocks_that_do_not_apply_are_rejected() {
+        let owner = Address::from_low_u64_be(1);
+        let chain = vec![block(
+            0,
+            vec![Transaction::genesis_tx(U256::from(100), owner, 0)],
+        )];
+        let overspend = Transaction::new(
+            owner,
+            Address::from_low_u64_be(2),
+            U256::from(500),
+            U256::zero(),
+            EMPTY_SIGNATURE(),
+            1000,
+            ADDRESS_ZERO(),
+        );
+
+        let mut confirmed = NodeMemory::replay(&chain).unwrap();
+        let verified = VerifiedCache::default();
+        assert!(block(1, vec![]).validate(&confirmed, &verified));
+        assert!(!block(5, vec![]).validate(&confirmed, &verified));
+        assert!(confirmed.process_block(&block(1, vec![overspend])).is_err());
+    }
+
+    #[test]
+    fn rebase_replays_the_mempool_on_the_new_block() {
+        let key = WalletKey::from_bytes(KeyType::Secp256k1, &[7u8; 32]).unwrap();
+        let owner = key.address();
+        let transfer = |to: u64, amount: u64, timestamp: u64, nonce: u64| {
+            let tx = Transaction::new(
+                owner,
+                Address::from_low_u64_be(to),
+                U256::from(amount),
+                U256::zero(),
+                EMPTY_SIGNATURE(),
+                timestamp,
+                ADDRESS_ZERO(),
+            );
+            key.sign_transaction(tx, &U256::from(nonce)).unwrap()
+        };
+        let chain = vec![block(
+            0,
+            vec![Transaction::genesis_tx(U256::from(100), owner, 0)],
+        )];
+        let expired = transfer(2, 30, 0, 0);
+        let pending = transfer(4, 5, current_timestamp(), 1);
+        // Spends the expired transaction's nonce, and never went through the mempool
+        let unseen = block(1, vec![transfer(3, 10, 0, 0)]);
+
+        let mut live = NodeMemory::replay(&chain).unwrap();
+        for tx in [&expired, &pending] {
+            live.apply_transaction(&ADDRESS_ZERO(), tx, 0).unwrap();
+            live.push_to_mempool(tx);
+        }
+        let mut confirmed = NodeMemory::replay(&chain).unwrap();
+        confirmed.process_block(&unseen).unwrap();
+        live.include_block(&unseen);
+        live.rebase(confirmed);
+
+        assert_eq!(live.balance_of(&owner), U256::from(85));
+        assert_eq!(live.balance_of(&Address::from_low_u64_be(2)), U256::zero());
+        assert_eq!(
+            live.balance_of(&Address::from_low_u64_be(3)),
+            U256::from(10)
+        );
+        assert_eq!(live.current_nonce(&owner), U256::from(2));
+        assert!(matches!(
+            live.transaction_status(&expired.id()),
+            Some(TransactionStatus::Dropped)
+        ));
+        assert_eq!(live.mempool_transactions().len(), 1);
+    }
+
+    #[test]
+    fn only_the_genesis_block_mints() {
+        let mint = || Transaction::genesis_tx(U256::from(1000), Address::from_low_u64_be(1), 0);
+        let mem = NodeMemory::new();
+        let verified = VerifiedCache::default();
+
+        assert!(mem
+            .verify_block_signatures(&block(0, vec![mint()]), &verified)
+            .is_ok());
+        assert!(mem
+            .verify_block_signatures(&block(5, vec![mint()]), &verified)
+            .is_err());
+    }
+}
@@ -1,3 +1,5 @@
 pub mod core;
+pub mod fees;
+pub mod history;
 pub mod memory;
 pub mod node_proto;
\ No newline at end of file
@@ -13,7 +13,10 @@ use node_proto::{
 pub async fn connect_node(client_address: Option<SocketAddr>) -> Result<(), ()> {
     if let Some(addr) = client_address {
         let addr: String = addr.ip().to_string();
-        let mut client = NodeClient::connect(addr.clone()).await.unwrap();
+        // Wallets and other plain clients are not nodes, so there is nothing to register
+        let Ok(mut client) = NodeClient::connect(addr.clone()).await else {
+            return Ok(());
+        };
         let res = client
             .request_node_info(Request::new(NodeInfoRequest {}))
             .await;
@@ -36,7 +39,9 @@ pub async fn propagate_transaction(req:TransactionRequest) -> Result<(), ()> {
     let nodes = read_from_file("data/", "node_data.json").unwrap();
     let nodes: Vec<String> = serde_json::from_str(&nodes).unwrap();
     for node in nodes.iter() {
-        let mut client = NodeClient::connect(node.clone()).await.unwrap();
+        let Ok(mut client) = NodeClient::connect(node.clone()).await else {
+            continue;
+        };
         forget(client.request_send_transaction(Request::new(req.clone())).await);
     }
     Ok(())
@@ -46,7 +51,9 @@ pub async fn propagate_block(req:AddBlockRequest) -> Result<(), ()> {
     let nodes = read_from_file("data/", "node_data.json").unwrap();
     let nodes: Vec<String> = serde_json::from_str(&nodes).unwrap();
     for node in nodes.iter() {
-        let mut client = NodeClient::connect(node.clone()).await.unwrap();
+        let Ok(mut client) = NodeClient::connect(node.clone()).await else {
+            continue;
+        };
         forget(client.request_add_block(Request::new(req.clone())).await);
     }
     Ok(())
@@ -0,0 +1,107 @@
+use ethers::types::{Address, Signature, U256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread;
+
+use super::{
+    multisig::MultisigAccount,
+    verification::{verify_ed25519, verify_multisig, verify_signature, SignatureError},
+};
+
+// Below this many checks spawning threads costs more than it saves
+const MIN_PARALLEL_BATCH: usize = 16;
+// The cache is cleared once it grows past this many entries
+const VERIFIED_CACHE_LIMIT: usize = 100_000;
+
+#[derive(Debug, Clone)]
+pub enum SignatureCheck {
+    Single {
+        msg: String,
+        signature: Signature,
+        signer: Address,
+    },
+    Multisig {
+        msg: String,
+        signatures: Vec<Signature>,
+        account: MultisigAccount,
+    },
+    Ed25519 {
+        msg: String,
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+        signer: Address,
+    },
+}
+
+impl SignatureCheck {
+    pub fn verify(&self) -> Result<(), SignatureError> {
+        match self {
+            SignatureCheck::Single {
+                msg,
+                signature,
+                signer,
+            } => verify_signature(msg, signature, signer),
+            SignatureCheck::Multisig {
+                msg,
+                signatures,
+                account,
+            } => verify_multisig(msg, signatures, account),
+            SignatureCheck::Ed25519 {
+                msg,
+                public_key,
+                signature,
+                signer,
+            } => verify_ed25519(msg, public_key, signature, signer),
+        }
+    }
+}
+
+/// Runs the checks spread over all available cores, failing with the first error found.
+pub fn verify_batch(checks: &[SignatureCheck]) -> Result<(), SignatureError> {
+    if checks.len() < MIN_PARALLEL_BATCH {
+        return checks.iter().try_for_each(SignatureCheck::verify);
+    }
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = checks.len().div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = checks
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().try_for_each(SignatureCheck::verify)))
+            .collect();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap())
+    })
+}
+
+/// Transactions whose signatures already passed, keyed by (tx id, sender nonce) so a
+/// cached entry never vouches for the same transaction at another nonce.
+#[derive(Debug, Default)]
+pub struct VerifiedCache {
+    entries: Mutex<HashSet<(String, U256)>>,
+}
+
+impl VerifiedCache {
+    pub fn insert(&self, tx_id: String, nonce: U256) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= VERIFIED_CACHE_LIMIT {
+            entries.clear();
+        }
+        entries.insert((tx_id, nonce));
+    }
+
+    pub fn contains(&self, tx_id: &str, nonce: &U256) -> bool {
+        self.entries
+            .lock()
+            .unwrap()
+            .contains(&(tx_id.to_string(), *nonce))
+    }
+
+    pub fn remove(&self, tx_id: &str, nonce: &U256) {
+        self.entries
+            .lock()
+            .unwrap()
+            .remove(&(tx_id.to_string(), *nonce));
+    }
+}
@@ -1 +1,3 @@
+pub mod batch;
+pub mod multisig;
 pub mod verification;
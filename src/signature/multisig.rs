@@ -0,0 +1,49 @@
+use ethers::{
+    types::{Address, U256},
+    utils::keccak256,
+};
+use serde_derive::{Deserialize, Serialize};
+
+// Members cosign with recoverable secp256k1 signatures, ed25519 accounts can't be one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultisigAccount {
+    signers: Vec<Address>,
+    threshold: u8,
+}
+
+impl MultisigAccount {
+    pub fn new(signers: Vec<Address>, threshold: u8) -> MultisigAccount {
+        MultisigAccount { signers, threshold }
+    }
+
+    pub fn signers(&self) -> &Vec<Address> {
+        &self.signers
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// A signer set is usable when it has no duplicates and 1 <= threshold <= signers.
+    pub fn is_valid(&self) -> bool {
+        let mut signers = self.signers.clone();
+        signers.sort();
+        signers.dedup();
+
+        signers.len() == self.signers.len()
+            && self.threshold > 0
+            && self.threshold as usize <= self.signers.len()
+    }
+
+    /// Address of the account registered by `creator` at the given nonce.
+    pub fn derive_address(creator: &Address, nonce: &U256) -> Address {
+        let mut nonce_bytes = [0u8; 32];
+        nonce.to_big_endian(&mut nonce_bytes);
+
+        let mut preimage = b"zrush_multisig:".to_vec();
+        preimage.extend_from_slice(creator.as_bytes());
+        preimage.extend_from_slice(&nonce_bytes);
+
+        Address::from_slice(&keccak256(preimage)[12..])
+    }
+}
@@ -1,6 +1,255 @@
-use ethers::types::{Address, Signature};
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey};
+use std::fmt;
 
-pub fn verify_signature(msg: &str, signature: &Signature, from: &Address) -> bool {
-    let recover_addr = signature.recover(msg).unwrap();
-    recover_addr == *from
+use ethers::{
+    types::{Address, Signature, U256},
+    utils::keccak256,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use super::multisig::MultisigAccount;
+
+// Order of the secp256k1 curve
+const SECP256K1_N: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+// Kind of key an account signs with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum KeyType {
+    #[default]
+    Secp256k1,
+    Ed25519,
+}
+
+/// How the sender signed a transaction.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    // Recoverable ECDSA signature carried in the transaction's `signature`
+    #[default]
+    Secp256k1,
+    // Ed25519 signatures can't be recovered, so the key travels along with them
+    Ed25519 {
+        public_key: Vec<u8>,
+        signature: Vec<u8>,
+    },
+}
+
+#[derive(Debug)]
+pub enum SignatureError {
+    // Only the 27/28 recovery ids produced by message signing are accepted
+    InvalidRecoveryId(u64),
+    // `r` or `s` is zero or not below the curve order
+    OutOfRange,
+    // `s` in the upper half of the curve order, i.e. a malleated signature
+    HighS,
+    RecoveryFailed(String),
+    WrongSigner {
+        expected: Address,
+        recovered: Address,
+    },
+    ThresholdNotMet {
+        required: u8,
+        signed: usize,
+    },
+    // More signatures than the threshold, extra ones would change the transaction id
+    SurplusSignatures {
+        required: u8,
+        signed: usize,
+    },
+    NotASigner(Address),
+    // Duplicated signer or signatures not in the order of the account's signer set
+    NonCanonicalOrder,
+    InvalidPublicKey,
+    // Malformed ed25519 signature or one that doesn't match the key and message
+    Ed25519Rejected,
+    // Sent from the zero address outside the genesis block
+    UnsignedTransaction,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignatureError::InvalidRecoveryId(v) => write!(f, "Invalid recovery id {v}"),
+            SignatureError::OutOfRange => write!(f, "Signature out of range"),
+            SignatureError::HighS => write!(f, "Signature is not in low-s form"),
+            SignatureError::RecoveryFailed(err) => write!(f, "Could not recover signer: {err}"),
+            SignatureError::WrongSigner {
+                expected,
+                recovered,
+            } => write!(f, "Signed by {:?} instead of {:?}", recovered, expected),
+            SignatureError::ThresholdNotMet { required, signed } => {
+                write!(f, "{signed} of {required} required signatures")
+            }
+            SignatureError::SurplusSignatures { required, signed } => {
+                write!(f, "{signed} signatures where {required} are required")
+            }
+            SignatureError::NotASigner(addr) => write!(f, "{:?} is not a signer", addr),
+            SignatureError::NonCanonicalOrder => {
+                write!(f, "Signatures are duplicated or out of signer order")
+            }
+            SignatureError::InvalidPublicKey => write!(f, "Invalid public key"),
+            SignatureError::Ed25519Rejected => write!(f, "Invalid ed25519 signature"),
+            SignatureError::UnsignedTransaction => {
+                write!(f, "Unsigned transaction outside the genesis block")
+            }
+        }
+    }
+}
+
+/// Rejects signatures that are malformed or not in canonical (low-s) form before
+/// any recovery is attempted.
+pub fn check_signature(signature: &Signature) -> Result<(), SignatureError> {
+    if signature.v != 27 && signature.v != 28 {
+        return Err(SignatureError::InvalidRecoveryId(signature.v));
+    }
+    let n = U256::from(SECP256K1_N);
+    if signature.r.is_zero() || signature.s.is_zero() || signature.r >= n || signature.s >= n {
+        return Err(SignatureError::OutOfRange);
+    }
+    if signature.s > n / 2 {
+        return Err(SignatureError::HighS);
+    }
+    Ok(())
+}
+
+pub fn recover_signer(msg: &str, signature: &Signature) -> Result<Address, SignatureError> {
+    check_signature(signature)?;
+    signature
+        .recover(msg)
+        .map_err(|err| SignatureError::RecoveryFailed(err.to_string()))
+}
+
+pub fn verify_signature(
+    msg: &str,
+    signature: &Signature,
+    from: &Address,
+) -> Result<(), SignatureError> {
+    let recovered = recover_signer(msg, signature)?;
+    if recovered != *from {
+        return Err(SignatureError::WrongSigner {
+            expected: *from,
+            recovered,
+        });
+    }
+    Ok(())
+}
+
+/// Address of an ed25519 key. The prefix keeps it apart from secp256k1 addresses.
+pub fn derive_ed25519_address(public_key: &[u8; 32]) -> Address {
+    let mut preimage = b"zrush_ed25519:".to_vec();
+    preimage.extend_from_slice(public_key);
+    Address::from_slice(&keccak256(preimage)[12..])
+}
+
+/// Checks an ed25519 signature over `msg` and that the key belongs to `from`.
+/// Verification is strict so small-order keys and malleated signatures are refused.
+pub fn verify_ed25519(
+    msg: &str,
+    public_key: &[u8],
+    signature: &[u8],
+    from: &Address,
+) -> Result<(), SignatureError> {
+    let public_key: [u8; 32] = public_key
+        .try_into()
+        .map_err(|_| SignatureError::InvalidPublicKey)?;
+    let key =
+        VerifyingKey::from_bytes(&public_key).map_err(|_| SignatureError::InvalidPublicKey)?;
+    let recovered = derive_ed25519_address(&public_key);
+    if recovered != *from {
+        return Err(SignatureError::WrongSigner {
+            expected: *from,
+            recovered,
+        });
+    }
+
+    let signature =
+        Ed25519Signature::from_slice(signature).map_err(|_| SignatureError::Ed25519Rejected)?;
+    key.verify_strict(msg.as_bytes(), &signature)
+        .map_err(|_| SignatureError::Ed25519Rejected)
+}
+
+/// Checks that exactly `threshold` distinct members of the account signed `msg`, in
+/// the order of the account's signer set. Only one encoding of a signature set is
+/// accepted so relayers can't change the transaction id.
+pub fn verify_multisig(
+    msg: &str,
+    signatures: &[Signature],
+    account: &MultisigAccount,
+) -> Result<(), SignatureError> {
+    let required = account.threshold();
+    if signatures.len() > required as usize {
+        return Err(SignatureError::SurplusSignatures {
+            required,
+            signed: signatures.len(),
+        });
+    }
+
+    let mut last_position = None;
+    for signature in signatures.iter() {
+        let signer = recover_signer(msg, signature)?;
+        let position = account
+            .signers()
+            .iter()
+            .position(|member| *member == signer)
+            .ok_or(SignatureError::NotASigner(signer))?;
+        if last_position.is_some_and(|last| position <= last) {
+            return Err(SignatureError::NonCanonicalOrder);
+        }
+        last_position = Some(position);
+    }
+
+    if signatures.len() < required as usize {
+        return Err(SignatureError::ThresholdNotMet {
+            required,
+            signed: signatures.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wallet::core::WalletKey;
+
+    use super::{verify_multisig, KeyType, MultisigAccount, SignatureError};
+
+    #[test]
+    fn multisig_accepts_only_the_canonical_signature_set() {
+        let keys: Vec<WalletKey> = (1..=3u8)
+            .map(|seed| WalletKey::from_bytes(KeyType::Secp256k1, &[seed; 32]).unwrap())
+            .collect();
+        let account = MultisigAccount::new(keys.iter().map(|key| key.address()).collect(), 2);
+        let msg = "zrush_signed_message:test";
+        let sig = |i: usize| keys[i].sign_message(msg).unwrap();
+
+        assert!(verify_multisig(msg, &[sig(0), sig(2)], &account).is_ok());
+        assert!(matches!(
+            verify_multisig(msg, &[sig(2), sig(0)], &account),
+            Err(SignatureError::NonCanonicalOrder)
+        ));
+        assert!(matches!(
+            verify_multisig(msg, &[sig(0), sig(0)], &account),
+            Err(SignatureError::NonCanonicalOrder)
+        ));
+        assert!(matches!(
+            verify_multisig(msg, &[sig(0), sig(1), sig(2)], &account),
+            Err(SignatureError::SurplusSignatures { .. })
+        ));
+
+        let outsider = WalletKey::from_bytes(KeyType::Secp256k1, &[9u8; 32]).unwrap();
+        assert!(matches!(
+            verify_multisig(
+                msg,
+                &[sig(0), outsider.sign_message(msg).unwrap()],
+                &account
+            ),
+            Err(SignatureError::NotASigner(_))
+        ));
+        assert!(matches!(
+            verify_multisig(msg, &[sig(1)], &account),
+            Err(SignatureError::ThresholdNotMet { .. })
+        ));
+    }
 }
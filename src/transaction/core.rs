@@ -1,11 +1,150 @@
-use ethers::types::{Address, Signature, U256};
+use ethers::{
+    types::{Address, Signature, H256, U256},
+    utils::keccak256,
+};
 use serde_derive::{Deserialize, Serialize};
 
 use crate::{
+    asset::core::{AssetId, TokenMetadata},
     node::memory::NodeMemory,
-    utils::ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE, U256_ZERO},
+    signature::{batch::SignatureCheck, multisig::MultisigAccount, verification::SignatureScheme},
+    utils::{
+        ethers_empty_types::{ADDRESS_ZERO, EMPTY_SIGNATURE, U256_ZERO},
+        timestamp::current_timestamp,
+    },
+    vm::core::GAS_PRICE,
 };
 
+use super::lock::LockSchedule;
+
+// Largest memo a transaction may carry, in bytes
+pub const MAX_DATA_SIZE: usize = 256;
+// Extra fee charged for every byte of memo
+pub const FEE_PER_DATA_BYTE: u64 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferOutput {
+    to: Address,
+    amount: U256,
+}
+
+impl TransferOutput {
+    pub fn new(to: Address, amount: U256) -> TransferOutput {
+        TransferOutput { to, amount }
+    }
+
+    pub fn to(&self) -> &Address {
+        &self.to
+    }
+
+    pub fn amount(&self) -> &U256 {
+        &self.amount
+    }
+}
+
+// A second account that pays the fee on behalf of the sender
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sponsorship {
+    sponsor: Address,
+    signature: Signature,
+    #[serde(default)]
+    scheme: SignatureScheme,
+}
+
+impl Sponsorship {
+    pub fn new(sponsor: Address, signature: Signature) -> Sponsorship {
+        Sponsorship {
+            sponsor,
+            signature,
+            scheme: SignatureScheme::Secp256k1,
+        }
+    }
+
+    pub fn with_scheme(mut self, scheme: SignatureScheme) -> Sponsorship {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn scheme(&self) -> &SignatureScheme {
+        &self.scheme
+    }
+
+    pub fn sponsor(&self) -> &Address {
+        &self.sponsor
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum TransactionKind {
+    #[default]
+    Transfer,
+    // Pays every output under a single signature and nonce
+    BatchTransfer(Vec<TransferOutput>),
+    // Registers a M-of-N account owned by the listed signers
+    CreateMultisig(MultisigAccount),
+    // Credits the receiver with funds it can only spend once the schedule releases them
+    LockedTransfer(LockSchedule),
+    // Creates a new token and credits the whole supply to the sender
+    IssueToken {
+        metadata: TokenMetadata,
+        supply: U256,
+    },
+    // Moves `amount` of the token to `to`, the fee is still paid in the native coin
+    TransferToken(AssetId),
+    // Destroys `amount` of the sender's token balance
+    BurnToken(AssetId),
+    // Stores contract code at an address derived from the sender and nonce
+    DeployContract(Vec<u8>),
+    // Sends `amount` to the `to` contract and runs its code with the input
+    CallContract {
+        input: Vec<u8>,
+        gas_limit: u64,
+    },
+    // Lets the spender move up to `amount` of the sender's coins, replacing any
+    // previous allowance
+    Approve(Address),
+    // Signed by a spender to move `amount` from the owner's balance to `to`
+    TransferFrom(Address),
+    // Escrows `amount` for `to` until the preimage of the hash is revealed or the
+    // timeout (milliseconds) passes
+    HtlcLock {
+        hash_lock: H256,
+        timeout: u64,
+    },
+    // Releases an HTLC to its recipient by revealing the preimage
+    HtlcClaim {
+        id: H256,
+        preimage: Vec<u8>,
+    },
+    // Returns an expired HTLC to its sender
+    HtlcRefund(H256),
+}
+
+impl TransactionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionKind::Transfer => "transfer",
+            TransactionKind::BatchTransfer(_) => "batch_transfer",
+            TransactionKind::CreateMultisig(_) => "create_multisig",
+            TransactionKind::LockedTransfer(_) => "locked_transfer",
+            TransactionKind::IssueToken { .. } => "issue_token",
+            TransactionKind::TransferToken(_) => "transfer_token",
+            TransactionKind::BurnToken(_) => "burn_token",
+            TransactionKind::DeployContract(_) => "deploy_contract",
+            TransactionKind::CallContract { .. } => "call_contract",
+            TransactionKind::Approve(_) => "approve",
+            TransactionKind::TransferFrom(_) => "transfer_from",
+            TransactionKind::HtlcLock { .. } => "htlc_lock",
+            TransactionKind::HtlcClaim { .. } => "htlc_claim",
+            TransactionKind::HtlcRefund(_) => "htlc_refund",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transaction {
     from: Address,
@@ -15,6 +154,18 @@ pub struct Transaction {
     fee_receiver: Address,
     signature: Signature,
     timestamp: u64,
+    #[serde(default)]
+    kind: TransactionKind,
+    // Signatures of the members when spending from a multisig account
+    #[serde(default)]
+    cosignatures: Vec<Signature>,
+    // Free-form memo such as an invoice id or deposit tag
+    #[serde(default)]
+    data: Vec<u8>,
+    #[serde(default)]
+    sponsorship: Option<Sponsorship>,
+    #[serde(default)]
+    scheme: SignatureScheme,
 }
 
 impl Transaction {
@@ -35,6 +186,11 @@ impl Transaction {
             fee_receiver,
             signature,
             timestamp,
+            kind: TransactionKind::Transfer,
+            cosignatures: vec![],
+            data: vec![],
+            sponsorship: None,
+            scheme: SignatureScheme::Secp256k1,
         }
     }
 
@@ -50,6 +206,81 @@ impl Transaction {
         )
     }
 
+    pub fn batch_transfer(
+        from: Address,
+        outputs: Vec<TransferOutput>,
+        fee_amount: U256,
+        signature: Signature,
+        timestamp: u64,
+        fee_receiver: Address,
+    ) -> Transaction {
+        Transaction::new(
+            from,
+            ADDRESS_ZERO(),
+            U256_ZERO(),
+            fee_amount,
+            signature,
+            timestamp,
+            fee_receiver,
+        )
+        .with_kind(TransactionKind::BatchTransfer(outputs))
+    }
+
+    pub fn create_multisig(
+        from: Address,
+        account: MultisigAccount,
+        fee_amount: U256,
+        signature: Signature,
+        timestamp: u64,
+        fee_receiver: Address,
+    ) -> Transaction {
+        Transaction::new(
+            from,
+            ADDRESS_ZERO(),
+            U256_ZERO(),
+            fee_amount,
+            signature,
+            timestamp,
+            fee_receiver,
+        )
+        .with_kind(TransactionKind::CreateMultisig(account))
+    }
+
+    pub fn with_signature(mut self, signature: Signature) -> Transaction {
+        self.signature = signature;
+        self
+    }
+
+    pub fn with_cosignatures(mut self, cosignatures: Vec<Signature>) -> Transaction {
+        self.cosignatures = cosignatures;
+        self
+    }
+
+    pub fn with_data(mut self, data: Vec<u8>) -> Transaction {
+        self.data = data;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: TransactionKind) -> Transaction {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_sponsorship(mut self, sponsorship: Sponsorship) -> Transaction {
+        self.sponsorship = Some(sponsorship);
+        self
+    }
+
+    pub fn with_scheme(mut self, scheme: SignatureScheme) -> Transaction {
+        self.scheme = scheme;
+        self
+    }
+
+    pub fn with_timestamp(mut self, timestamp: u64) -> Transaction {
+        self.timestamp = timestamp;
+        self
+    }
+
     pub fn from(&self) -> &Address {
         &self.from
     }
@@ -72,11 +303,451 @@ impl Transaction {
         &self.signature
     }
 
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn cosignatures(&self) -> &Vec<Signature> {
+        &self.cosignatures
+    }
+
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    pub fn kind(&self) -> &TransactionKind {
+        &self.kind
+    }
+
+    pub fn scheme(&self) -> &SignatureScheme {
+        &self.scheme
+    }
+
+    pub fn sponsorship(&self) -> Option<&Sponsorship> {
+        self.sponsorship.as_ref()
+    }
+
+    /// Account the fee is charged to, the sponsor if there is one.
+    pub fn fee_payer(&self) -> &Address {
+        match &self.sponsorship {
+            Some(sponsorship) => &sponsorship.sponsor,
+            None => &self.from,
+        }
+    }
+
+    /// Hash identifying the transaction. The timestamp is left out since every node
+    /// stamps the transaction when it receives it.
+    pub fn id(&self) -> String {
+        let mut body = self.clone();
+        body.timestamp = 0;
+        let encoded = serde_json::to_vec(&body).unwrap();
+        format!("0x{}", hex::encode(keccak256(encoded)))
+    }
+
+    /// Every (receiver, amount) pair of native coin credited by this transaction.
+    pub fn outputs(&self) -> Vec<TransferOutput> {
+        match &self.kind {
+            TransactionKind::Transfer
+            | TransactionKind::LockedTransfer(_)
+            | TransactionKind::CallContract { .. } => {
+                vec![TransferOutput::new(*self.to(), *self.amount())]
+            }
+            TransactionKind::BatchTransfer(outputs) => outputs.clone(),
+            TransactionKind::CreateMultisig(_)
+            | TransactionKind::IssueToken { .. }
+            | TransactionKind::TransferToken(_)
+            | TransactionKind::BurnToken(_)
+            | TransactionKind::DeployContract(_)
+            | TransactionKind::Approve(_)
+            | TransactionKind::TransferFrom(_)
+            | TransactionKind::HtlcLock { .. }
+            | TransactionKind::HtlcClaim { .. }
+            | TransactionKind::HtlcRefund(_) => vec![],
+        }
+    }
+
+    /// Every address the transaction involves: sender, receivers, fee payer and the
+    /// owner or spender of an allowance.
+    pub fn parties(&self) -> Vec<Address> {
+        let mut parties = vec![self.from, self.to, *self.fee_payer()];
+        parties.extend(self.outputs().iter().map(|output| output.to));
+        match &self.kind {
+            TransactionKind::Approve(spender) => parties.push(*spender),
+            TransactionKind::TransferFrom(owner) => parties.push(*owner),
+            _ => {}
+        }
+        parties.sort();
+        parties.dedup();
+        parties
+    }
+
+    /// Sum of all outputs plus any amount put in escrow, `None` on overflow.
+    pub fn total_amount(&self) -> Option<U256> {
+        let escrowed = match &self.kind {
+            TransactionKind::HtlcLock { .. } => self.amount,
+            _ => U256_ZERO(),
+        };
+        self.outputs()
+            .iter()
+            .try_fold(escrowed, |acc, output| acc.checked_add(output.amount))
+    }
+
+    /// Everything debited from the sender: the outputs, plus the fee unless a sponsor
+    /// pays it. `None` on overflow.
+    pub fn total_cost(&self) -> Option<U256> {
+        match self.sponsorship {
+            Some(_) => self.total_amount(),
+            None => self.total_amount()?.checked_add(self.fee_amount),
+        }
+    }
+
+    /// Smallest fee accepted for this transaction.
+    pub fn required_fee(&self) -> U256 {
+        let data_fee = U256::from(self.data.len()) * U256::from(FEE_PER_DATA_BYTE);
+        match &self.kind {
+            TransactionKind::CallContract { gas_limit, .. } => {
+                data_fee + U256::from(*gas_limit) * U256::from(GAS_PRICE)
+            }
+            _ => data_fee,
+        }
+    }
+
+    /// Message the sender signs for the given account nonce. It commits to the fee so
+    /// nobody relaying the transaction can raise what the sender pays.
+    pub fn signing_message(&self, nonce: &U256) -> String {
+        let mut msg = format!("{}:fee:{:?}", self.body_message(nonce), self.fee_amount);
+        if !self.data.is_empty() {
+            msg = format!("{}:data:{}", msg, hex::encode(&self.data));
+        }
+        if let Some(sponsorship) = &self.sponsorship {
+            msg = format!("{}:sponsor:{:?}", msg, sponsorship.sponsor);
+        }
+        msg
+    }
+
+    /// Message the sponsor signs, it commits to the sender's message and fee so the
+    /// sponsorship can't be moved to another transaction.
+    pub fn sponsor_message(&self, nonce: &U256) -> String {
+        format!(
+            "zrush_signed_message:sponsor:{:?}:{}",
+            self.fee_amount,
+            self.signing_message(nonce)
+        )
+    }
+
+    /// Checks the transaction must pass when sent at `nonce`. `multisig` is the account
+    /// registered at the sender's address, if any.
+    pub fn signature_checks(
+        &self,
+        nonce: &U256,
+        multisig: Option<MultisigAccount>,
+    ) -> Vec<SignatureCheck> {
+        let msg = self.signing_message(nonce);
+        // Multisig accounts are spent with their members' signatures
+        let mut checks = vec![match multisig {
+            Some(account) => SignatureCheck::Multisig {
+                msg,
+                signatures: self.cosignatures.clone(),
+                account,
+            },
+            None => scheme_check(&self.scheme, msg, self.signature, self.from),
+        }];
+        if let Some(sponsorship) = &self.sponsorship {
+            checks.push(scheme_check(
+                &sponsorship.scheme,
+                self.sponsor_message(nonce),
+                sponsorship.signature,
+                sponsorship.sponsor,
+            ));
+        }
+        checks
+    }
+
+    // Fields are separated by `:` so adjacent numbers can't be split differently, e.g.
+    // amount 12 and nonce 3 against amount 1 and nonce 23
+    fn body_message(&self, nonce: &U256) -> String {
+        match &self.kind {
+            TransactionKind::Transfer => format!(
+                "zrush_signed_message:{:?}:{:?}:{:?}:{:?}",
+                self.from, self.to, self.amount, nonce
+            ),
+            TransactionKind::BatchTransfer(outputs) => {
+                let outputs: Vec<String> = outputs
+                    .iter()
+                    .map(|output| format!("{:?}:{:?}", output.to, output.amount))
+                    .collect();
+                format!(
+                    "zrush_signed_message:batch:{:?}:{}:{:?}",
+                    self.from,
+                    outputs.join(":"),
+                    nonce
+                )
+            }
+            TransactionKind::LockedTransfer(schedule) => format!(
+                "zrush_signed_message:locked:{:?}:{:?}:{:?}:{:?}:{:?}",
+                self.from, self.to, self.amount, schedule, nonce
+            ),
+            TransactionKind::CreateMultisig(account) => {
+                let signers: Vec<String> = account
+                    .signers()
+                    .iter()
+                    .map(|signer| format!("{:?}", signer))
+                    .collect();
+                format!(
+                    "zrush_signed_message:multisig:{:?}:{}:{}:{:?}",
+                    self.from,
+                    signers.join(":"),
+                    account.threshold(),
+                    nonce
+                )
+            }
+            // Quoted and escaped, so the name and symbol can't trade characters
+            TransactionKind::IssueToken { metadata, supply } => format!(
+                "zrush_signed_message:issue:{:?}:{:?}:{:?}:{:?}:{:?}",
+                self.from,
+                metadata.name(),
+                metadata.symbol(),
+                supply,
+                nonce
+            ),
+            TransactionKind::TransferToken(asset_id) => format!(
+                "zrush_signed_message:token:{:?}:{:?}:{:?}:{:?}:{:?}",
+                self.from, asset_id, self.to, self.amount, nonce
+            ),
+            TransactionKind::BurnToken(asset_id) => format!(
+                "zrush_signed_message:burn:{:?}:{:?}:{:?}:{:?}",
+                self.from, asset_id, self.amount, nonce
+            ),
+            TransactionKind::DeployContract(code) => format!(
+                "zrush_signed_message:deploy:{:?}:{}:{:?}",
+                self.from,
+                hex::encode(code),
+                nonce
+            ),
+            TransactionKind::Approve(spender) => format!(
+                "zrush_signed_message:approve:{:?}:{:?}:{:?}:{:?}",
+                self.from, spender, self.amount, nonce
+            ),
+            TransactionKind::TransferFrom(owner) => format!(
+                "zrush_signed_message:transfer_from:{:?}:{:?}:{:?}:{:?}:{:?}",
+                self.from, owner, self.to, self.amount, nonce
+            ),
+            TransactionKind::HtlcLock { hash_lock, timeout } => format!(
+                "zrush_signed_message:htlc_lock:{:?}:{:?}:{:?}:{:?}:{}:{:?}",
+                self.from, self.to, self.amount, hash_lock, timeout, nonce
+            ),
+            TransactionKind::HtlcClaim { id, preimage } => format!(
+                "zrush_signed_message:htlc_claim:{:?}:{:?}:{}:{:?}",
+                self.from,
+                id,
+                hex::encode(preimage),
+                nonce
+            ),
+            TransactionKind::HtlcRefund(id) => format!(
+                "zrush_signed_message:htlc_refund:{:?}:{:?}:{:?}",
+                self.from, id, nonce
+            ),
+            TransactionKind::CallContract { input, gas_limit } => format!(
+                "zrush_signed_message:call:{:?}:{:?}:{:?}:{}:{}:{:?}",
+                self.from,
+                self.to,
+                self.amount,
+                hex::encode(input),
+                gas_limit,
+                nonce
+            ),
+        }
+    }
+
     pub fn verify(&self, mem: &NodeMemory) -> bool {
         let from = self.from();
-        let balance_from = mem.balance_of(from);
-        let amount = self.amount();
+        let balance_from = mem.spendable_balance_of(from, current_timestamp());
+
+        if self.data.len() > MAX_DATA_SIZE || self.fee_amount < self.required_fee() {
+            return false;
+        }
+
+        if let TransactionKind::TransferToken(asset_id) | TransactionKind::BurnToken(asset_id) =
+            &self.kind
+        {
+            if mem.asset_balance_of(asset_id, from) < self.amount {
+                return false;
+            }
+        }
+
+        if let TransactionKind::TransferFrom(owner) = &self.kind {
+            if mem.allowance(owner, from) < self.amount
+                || mem.spendable_balance_of(owner, current_timestamp()) < self.amount
+            {
+                return false;
+            }
+        }
+
+        let now = current_timestamp();
+        let htlc_ok = match &self.kind {
+            TransactionKind::HtlcLock { timeout, .. } => *timeout > now,
+            TransactionKind::HtlcClaim { id, preimage } => mem
+                .htlc(id)
+                .is_some_and(|htlc| !htlc.is_expired(now) && htlc.matches(preimage)),
+            TransactionKind::HtlcRefund(id) => {
+                mem.htlc(id).is_some_and(|htlc| htlc.is_expired(now))
+            }
+            _ => true,
+        };
+        if !htlc_ok {
+            return false;
+        }
+
+        if let Some(sponsorship) = &self.sponsorship {
+            if sponsorship.sponsor == *from
+                || mem.spendable_balance_of(&sponsorship.sponsor, current_timestamp())
+                    < self.fee_amount
+            {
+                return false;
+            }
+        }
+
+        match self.total_cost() {
+            Some(cost) => balance_from >= cost,
+            None => false,
+        }
+    }
+}
+
+// Check of a signature made with `scheme`, the ECDSA `signature` is only used by
+// secp256k1 signers
+fn scheme_check(
+    scheme: &SignatureScheme,
+    msg: String,
+    signature: Signature,
+    signer: Address,
+) -> SignatureCheck {
+    match scheme {
+        SignatureScheme::Secp256k1 => SignatureCheck::Single {
+            msg,
+            signature,
+            signer,
+        },
+        SignatureScheme::Ed25519 {
+            public_key,
+            signature,
+        } => SignatureCheck::Ed25519 {
+            msg,
+            public_key: public_key.clone(),
+            signature: signature.clone(),
+            signer,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ed25519_dalek::{Signer, SigningKey};
+    use ethers::types::{Address, U256};
+
+    use crate::{
+        signature::{
+            batch::verify_batch,
+            verification::{derive_ed25519_address, KeyType, SignatureScheme},
+        },
+        utils::ethers_empty_types::EMPTY_SIGNATURE,
+        wallet::core::WalletKey,
+    };
+
+    use super::{Sponsorship, Transaction};
+
+    fn signed_transfer(key: &WalletKey, to: Address, nonce: &U256) -> Transaction {
+        let tx = Transaction::new(
+            key.address(),
+            to,
+            U256::from(100),
+            U256::from(1),
+            EMPTY_SIGNATURE(),
+            0,
+            Address::zero(),
+        );
+        key.sign_transaction(tx, nonce).unwrap()
+    }
+
+    fn test_key() -> WalletKey {
+        WalletKey::from_bytes(KeyType::Secp256k1, &[7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn changing_the_receiver_breaks_the_signature() {
+        let nonce = U256::from(3);
+        let to: Address = "0x1111000000000000000000000000000000002222"
+            .parse()
+            .unwrap();
+        let mut tx = signed_transfer(&test_key(), to, &nonce);
+        assert!(verify_batch(&tx.signature_checks(&nonce, None)).is_ok());
+
+        // Same first and last two bytes, which is all a truncated address shows
+        tx.to = "0x1111ffffffffffffffffffffffffffffffff2222"
+            .parse()
+            .unwrap();
+        assert!(verify_batch(&tx.signature_checks(&nonce, None)).is_err());
+    }
+
+    #[test]
+    fn changing_the_fee_breaks_the_signature() {
+        let nonce = U256::from(3);
+        let mut tx = signed_transfer(&test_key(), Address::from_low_u64_be(2), &nonce);
+        assert!(verify_batch(&tx.signature_checks(&nonce, None)).is_ok());
+
+        tx.fee_amount = U256::from(1_000_000);
+        assert!(verify_batch(&tx.signature_checks(&nonce, None)).is_err());
+    }
+
+    #[test]
+    fn amount_and_nonce_digits_cannot_shift() {
+        let tx = |amount: u64| {
+            Transaction::new(
+                Address::from_low_u64_be(1),
+                Address::from_low_u64_be(2),
+                U256::from(amount),
+                U256::from(1),
+                EMPTY_SIGNATURE(),
+                0,
+                Address::zero(),
+            )
+        };
+        assert_ne!(
+            tx(12).signing_message(&U256::from(3)),
+            tx(1).signing_message(&U256::from(23))
+        );
+    }
+
+    #[test]
+    fn ed25519_accounts_can_sponsor() {
+        let nonce = U256::from(3);
+        let sponsor = SigningKey::from_bytes(&[8u8; 32]);
+        let public_key = sponsor.verifying_key().to_bytes();
+        let tx = Transaction::new(
+            test_key().address(),
+            Address::from_low_u64_be(2),
+            U256::from(100),
+            U256::from(1),
+            EMPTY_SIGNATURE(),
+            0,
+            Address::zero(),
+        )
+        .with_sponsorship(Sponsorship::new(
+            derive_ed25519_address(&public_key),
+            EMPTY_SIGNATURE(),
+        ));
+        let tx = test_key().sign_transaction(tx, &nonce).unwrap();
 
-        balance_from >= *amount
+        let signature = sponsor.sign(tx.sponsor_message(&nonce).as_bytes());
+        let sponsorship = tx
+            .sponsorship
+            .clone()
+            .unwrap()
+            .with_scheme(SignatureScheme::Ed25519 {
+                public_key: public_key.to_vec(),
+                signature: signature.to_bytes().to_vec(),
+            });
+        let tx = tx.with_sponsorship(sponsorship);
+        assert!(verify_batch(&tx.signature_checks(&nonce, None)).is_ok());
     }
 }
@@ -0,0 +1,79 @@
+use ethers::{
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use serde_derive::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Largest preimage a claim may reveal, in bytes
+pub const MAX_PREIMAGE_SIZE: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Htlc {
+    sender: Address,
+    recipient: Address,
+    amount: U256,
+    hash_lock: H256,
+    // Timestamp (milliseconds) after which the sender can take the funds back
+    timeout: u64,
+}
+
+impl Htlc {
+    pub fn new(
+        sender: Address,
+        recipient: Address,
+        amount: U256,
+        hash_lock: H256,
+        timeout: u64,
+    ) -> Htlc {
+        Htlc {
+            sender,
+            recipient,
+            amount,
+            hash_lock,
+            timeout,
+        }
+    }
+
+    pub fn sender(&self) -> &Address {
+        &self.sender
+    }
+
+    pub fn recipient(&self) -> &Address {
+        &self.recipient
+    }
+
+    pub fn amount(&self) -> &U256 {
+        &self.amount
+    }
+
+    pub fn hash_lock(&self) -> &H256 {
+        &self.hash_lock
+    }
+
+    pub fn timeout(&self) -> u64 {
+        self.timeout
+    }
+
+    pub fn is_expired(&self, timestamp: u64) -> bool {
+        timestamp >= self.timeout
+    }
+
+    /// SHA-256 is used so the same hash can lock funds on other chains.
+    pub fn matches(&self, preimage: &[u8]) -> bool {
+        preimage.len() <= MAX_PREIMAGE_SIZE
+            && H256::from_slice(&Sha256::digest(preimage)) == self.hash_lock
+    }
+}
+
+/// Id of the HTLC created by `sender` at the given nonce.
+pub fn derive_htlc_id(sender: &Address, nonce: &U256) -> H256 {
+    let mut nonce_bytes = [0u8; 32];
+    nonce.to_big_endian(&mut nonce_bytes);
+
+    let mut preimage = b"zrush_htlc:".to_vec();
+    preimage.extend_from_slice(sender.as_bytes());
+    preimage.extend_from_slice(&nonce_bytes);
+
+    H256::from(keccak256(preimage))
+}
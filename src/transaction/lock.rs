@@ -0,0 +1,59 @@
+use ethers::types::{U256, U512};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::utils::ethers_empty_types::U256_ZERO;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LockSchedule {
+    // Whole amount is locked until the timestamp (milliseconds)
+    UntilTimestamp(u64),
+    // Whole amount is locked until the chain reaches the height
+    UntilHeight(U256),
+    // Nothing unlocks before `cliff`, then linearly from `start` until fully vested at `end`
+    Vesting { start: u64, cliff: u64, end: u64 },
+}
+
+impl LockSchedule {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            LockSchedule::Vesting { start, cliff, end } => start <= cliff && cliff <= end,
+            _ => true,
+        }
+    }
+
+    /// Part of `amount` still locked at the given time and height.
+    pub fn locked_amount(&self, amount: &U256, timestamp: u64, height: &U256) -> U256 {
+        match self {
+            LockSchedule::UntilTimestamp(unlock) if timestamp < *unlock => *amount,
+            LockSchedule::UntilHeight(unlock) if height < unlock => *amount,
+            LockSchedule::Vesting { start, cliff, end } => {
+                if timestamp < *cliff {
+                    *amount
+                } else if timestamp >= *end {
+                    U256_ZERO()
+                } else {
+                    let vested =
+                        amount.full_mul(U256::from(timestamp - start)) / U512::from(end - start);
+                    amount - U256::try_from(vested).unwrap()
+                }
+            }
+            _ => U256_ZERO(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeLock {
+    amount: U256,
+    schedule: LockSchedule,
+}
+
+impl TimeLock {
+    pub fn new(amount: U256, schedule: LockSchedule) -> TimeLock {
+        TimeLock { amount, schedule }
+    }
+
+    pub fn locked_amount(&self, timestamp: u64, height: &U256) -> U256 {
+        self.schedule.locked_amount(&self.amount, timestamp, height)
+    }
+}
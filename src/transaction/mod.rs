@@ -1 +1,4 @@
 pub mod core;
+pub mod htlc;
+pub mod lock;
+pub mod receipt;
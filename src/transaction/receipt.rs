@@ -0,0 +1,15 @@
+use ethers::types::U256;
+
+#[derive(Debug, Clone)]
+pub enum TransactionStatus {
+    // Accepted by this node and waiting in the mempool
+    Pending,
+    Included {
+        block_hash: String,
+        height: U256,
+        index: u64,
+    },
+    // Left the mempool without being included in a block
+    Dropped,
+    Failed(String),
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{stdin, IsTerminal};
+
+#[derive(Debug)]
+pub enum InputError {
+    IoError(String),
+    // Not given up front while `--yes` forbids asking for it
+    MissingValue(String),
+    InvalidInput(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::IoError(msg) | InputError::InvalidInput(msg) => write!(f, "{msg}"),
+            InputError::MissingValue(key) => {
+                write!(f, "Missing {key}, pass it with {}", value_source(key))
+            }
+        }
+    }
+}
+
+// Secrets have no flag of their own, they would show up in the process list
+fn value_source(key: &str) -> String {
+    match key {
+        "password" => String::from("--password-file or ZRUSH_PASSWORD"),
+        "new-password" => String::from("--new-password-file or ZRUSH_NEW_PASSWORD"),
+        "private-key" => String::from("ZRUSH_PRIVATE_KEY"),
+        "mnemonic" => String::from("ZRUSH_MNEMONIC"),
+        key => format!("--{key}"),
+    }
+}
+
+/// Answers to the questions a command would otherwise ask on stdin, keyed by the
+/// name of the flag that provides them. Unanswered questions are prompted for,
+/// unless `assume_yes` is set: then confirmations pass and missing answers fail.
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    answers: HashMap<String, String>,
+    lists: HashMap<String, Vec<String>>,
+    assume_yes: bool,
+}
+
+impl Input {
+    pub fn new(assume_yes: bool) -> Input {
+        Input {
+            assume_yes,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_answer(mut self, key: &str, answer: String) -> Input {
+        self.answers.insert(key.to_string(), answer);
+        self
+    }
+
+    pub fn with_list(mut self, key: &str, answers: Vec<String>) -> Input {
+        self.lists.insert(key.to_string(), answers);
+        self
+    }
+
+    pub fn text(&self, key: &str, message: &str) -> Result<String, InputError> {
+        match self.answers.get(key) {
+            Some(answer) => Ok(answer.clone()),
+            None => self.ask(key, message, false),
+        }
+    }
+
+    /// Like `text`, but left empty instead of failing under `--yes`.
+    pub fn optional_text(&self, key: &str, message: &str) -> Result<String, InputError> {
+        if self.assume_yes && !self.answers.contains_key(key) {
+            return Ok(String::new());
+        }
+        self.text(key, message)
+    }
+
+    /// Secret answer, not echoed when typed on a terminal.
+    pub fn password(&self, key: &str, message: &str) -> Result<String, InputError> {
+        match self.answers.get(key) {
+            Some(answer) => Ok(answer.clone()),
+            None => self.ask(key, message, true),
+        }
+    }
+
+    /// A password being set, typed twice when prompted for.
+    pub fn new_password(&self, key: &str) -> Result<String, InputError> {
+        if let Some(answer) = self.answers.get(key) {
+            return Ok(answer.clone());
+        }
+        let password = self.ask(key, "Wallet password: ", true)?;
+        if self.ask(key, "Repeat password: ", true)? != password {
+            return Err(InputError::InvalidInput(String::from(
+                "Passwords do not match",
+            )));
+        }
+        println!("Do not forget your password!");
+        Ok(password)
+    }
+
+    /// Answers given one per line, until an empty one.
+    pub fn lines(&self, key: &str, message: &str) -> Result<Vec<String>, InputError> {
+        if let Some(answers) = self.lists.get(key) {
+            return Ok(answers.clone());
+        }
+        if self.assume_yes {
+            return Ok(vec![]);
+        }
+        println!("{message}");
+        let mut lines = vec![];
+        loop {
+            let line = read_line()?;
+            if line.trim().is_empty() {
+                return Ok(lines);
+            }
+            lines.push(line);
+        }
+    }
+
+    pub fn confirm(&self, message: &str) -> Result<bool, InputError> {
+        if self.assume_yes {
+            return Ok(true);
+        }
+        println!("{message} [y/N]");
+        Ok(matches!(read_line()?.trim(), "y" | "Y" | "yes"))
+    }
+
+    fn ask(&self, key: &str, message: &str, hidden: bool) -> Result<String, InputError> {
+        if self.assume_yes {
+            return Err(InputError::MissingValue(key.to_string()));
+        }
+        println!("{message}");
+        if hidden && stdin().is_terminal() {
+            return rpassword::read_password().map_err(|err| InputError::IoError(err.to_string()));
+        }
+        read_line()
+    }
+}
+
+/// First line of a file holding a password, as given to `--password-file`.
+pub fn read_password_file(path: &str) -> Result<String, InputError> {
+    let content =
+        fs::read_to_string(path).map_err(|err| InputError::IoError(format!("{path}: {err}")))?;
+    Ok(content.lines().next().unwrap_or_default().to_string())
+}
+
+fn read_line() -> Result<String, InputError> {
+    let mut buf = String::new();
+    stdin()
+        .read_line(&mut buf)
+        .map_err(|_| InputError::IoError(String::from("stdin: Failed to read input")))?;
+    Ok(buf.trim_end_matches(['\n', '\r']).to_string())
+}
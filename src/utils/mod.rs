@@ -1,4 +1,6 @@
 pub mod banner;
 pub mod ethers_empty_types;
 pub mod files;
+pub mod input;
 pub mod timestamp;
+pub mod units;
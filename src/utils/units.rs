@@ -0,0 +1,152 @@
+use ethers::types::U256;
+
+/// Decimals of chains created with no explicit setting.
+pub const DEFAULT_DECIMALS: u8 = 18;
+// 10^77 is the largest power of ten a U256 holds
+pub const MAX_DECIMALS: u8 = 77;
+
+/// Named units an amount can be suffixed with, as the number of decimals they sit
+/// below one coin. `base` is always the smallest indivisible unit.
+pub const NAMED_UNITS: [(&str, u8); 3] = [("zrush", 0), ("milli", 3), ("micro", 6)];
+pub const BASE_UNIT: &str = "base";
+
+/// Decimals of the unit `name` on a chain with `decimals`, `None` if it doesn't
+/// exist or is smaller than the base unit.
+pub fn unit_decimals(name: &str, decimals: u8) -> Option<u8> {
+    if name == BASE_UNIT {
+        return Some(0);
+    }
+    NAMED_UNITS
+        .iter()
+        .find(|(unit, _)| *unit == name)
+        .and_then(|(_, below)| decimals.checked_sub(*below))
+}
+
+/// Parses a decimal amount such as `1.5`, `250 milli` or `42base` into base units.
+/// A number without a unit is in whole coins.
+pub fn parse_amount(input: &str, decimals: u8) -> Result<U256, &'static str> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number = number.trim();
+    let unit_decimals = match unit {
+        "" => decimals,
+        unit => unit_decimals(unit, decimals).ok_or("Unknown unit")?,
+    };
+
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return Err("Missing amount");
+    }
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err("Invalid amount");
+    }
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.len() > unit_decimals as usize {
+        return Err("Amount has more decimals than the unit allows");
+    }
+
+    let parse = |digits: &str| match digits {
+        "" => Ok(U256::zero()),
+        digits => U256::from_dec_str(digits).map_err(|_| "Amount is too large"),
+    };
+    let whole = parse(whole)?
+        .checked_mul(pow10(unit_decimals)?)
+        .ok_or("Amount is too large")?;
+    let fraction = parse(fraction)?
+        .checked_mul(pow10(unit_decimals - fraction.len() as u8)?)
+        .ok_or("Amount is too large")?;
+    whole.checked_add(fraction).ok_or("Amount is too large")
+}
+
+/// Formats base units as whole coins, without trailing zeros: `1500000` with 6
+/// decimals is `1.5`.
+pub fn format_amount(amount: &U256, decimals: u8) -> String {
+    let Ok(unit) = pow10(decimals) else {
+        return amount.to_string();
+    };
+    let whole = amount / unit;
+    let fraction = (amount % unit).to_string();
+    if fraction == "0" {
+        return whole.to_string();
+    }
+    let padded = format!("{:0>width$}", fraction, width = decimals as usize);
+    format!("{whole}.{}", padded.trim_end_matches('0'))
+}
+
+fn pow10(exponent: u8) -> Result<U256, &'static str> {
+    if exponent > MAX_DECIMALS {
+        return Err("Too many decimals");
+    }
+    Ok(U256::exp10(exponent as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use super::{format_amount, parse_amount};
+
+    #[test]
+    fn rejects_more_decimals_than_the_unit_has() {
+        assert!(parse_amount("1.0000001", 6).is_err());
+        assert!(parse_amount("1.5 micro", 6).is_err());
+        // Trailing zeros carry no value
+        assert_eq!(parse_amount("1.500000000", 6), Ok(U256::from(1_500_000)));
+    }
+
+    #[test]
+    fn parses_named_units() {
+        assert_eq!(parse_amount("250 milli", 6), Ok(U256::from(250_000)));
+        assert_eq!(parse_amount("3micro", 6), Ok(U256::from(3)));
+        assert_eq!(parse_amount("2 zrush", 6), Ok(U256::from(2_000_000)));
+        assert_eq!(parse_amount("42base", 6), Ok(U256::from(42)));
+        assert!(parse_amount("1 micro", 3).is_err());
+        assert!(parse_amount("1 kilo", 6).is_err());
+    }
+
+    #[test]
+    fn accepts_a_leading_or_trailing_point() {
+        assert_eq!(parse_amount(".5", 2), Ok(U256::from(50)));
+        assert_eq!(parse_amount("5.", 2), Ok(U256::from(500)));
+        assert!(parse_amount(".", 2).is_err());
+    }
+
+    #[test]
+    fn rejects_an_empty_amount() {
+        assert!(parse_amount("", 18).is_err());
+        assert!(parse_amount("  ", 18).is_err());
+        assert!(parse_amount("milli", 18).is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_above_u256() {
+        let max = U256::MAX.to_string();
+        assert_eq!(parse_amount(&format!("{max} base"), 18), Ok(U256::MAX));
+        assert!(parse_amount(&format!("{max}0 base"), 18).is_err());
+        assert!(parse_amount(&max, 18).is_err());
+    }
+
+    #[test]
+    fn formatted_amounts_parse_back() {
+        for decimals in [0, 18] {
+            for amount in [
+                U256::zero(),
+                U256::one(),
+                U256::from(1_500_000_000_000_000_000u64),
+                U256::MAX,
+            ] {
+                let formatted = format_amount(&amount, decimals);
+                assert_eq!(parse_amount(&formatted, decimals), Ok(amount));
+            }
+        }
+        assert_eq!(format_amount(&U256::from(1_500_000), 6), "1.5");
+        assert_eq!(format_amount(&U256::from(15), 0), "15");
+    }
+}
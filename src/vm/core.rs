@@ -0,0 +1,408 @@
+use std::fmt;
+
+use ethers::{
+    types::{Address, U256},
+    utils::keccak256,
+};
+
+use super::opcode::*;
+
+// Largest contract accepted on deploy, in bytes
+pub const MAX_CODE_SIZE: usize = 24 * 1024;
+// Highest gas limit a single call may ask for
+pub const MAX_GAS_LIMIT: u64 = 1_000_000;
+// Native coin charged per unit of gas limit
+pub const GAS_PRICE: u64 = 1;
+
+const MAX_STACK_SIZE: usize = 1024;
+
+#[derive(Debug)]
+pub enum VmError {
+    OutOfGas,
+    StackUnderflow,
+    StackOverflow,
+    InvalidOpcode(u8),
+    InvalidJump,
+    InsufficientBalance,
+    Revert,
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VmError::OutOfGas => write!(f, "Out of gas"),
+            VmError::StackUnderflow => write!(f, "Stack underflow"),
+            VmError::StackOverflow => write!(f, "Stack overflow"),
+            VmError::InvalidOpcode(opcode) => write!(f, "Invalid opcode 0x{:02x}", opcode),
+            VmError::InvalidJump => write!(f, "Invalid jump"),
+            VmError::InsufficientBalance => write!(f, "Insufficient balance"),
+            VmError::Revert => write!(f, "Reverted"),
+        }
+    }
+}
+
+/// State a contract can reach while it runs.
+pub trait Host {
+    fn storage(&self, contract: &Address, key: &U256) -> U256;
+    fn set_storage(&mut self, contract: &Address, key: &U256, value: &U256);
+    fn balance(&self, addr: &Address) -> U256;
+    fn transfer(&mut self, from: &Address, to: &Address, amount: &U256) -> bool;
+}
+
+#[derive(Debug)]
+pub struct ExecutionResult {
+    pub gas_used: u64,
+    pub output: Option<U256>,
+}
+
+/// Parameters of a single contract call.
+pub struct CallContext<'a> {
+    pub contract: Address,
+    pub caller: Address,
+    pub value: U256,
+    pub input: &'a [u8],
+    pub gas_limit: u64,
+}
+
+/// Address of the contract deployed by `creator` at the given nonce.
+pub fn derive_contract_address(creator: &Address, nonce: &U256) -> Address {
+    let mut nonce_bytes = [0u8; 32];
+    nonce.to_big_endian(&mut nonce_bytes);
+
+    let mut preimage = b"zrush_contract:".to_vec();
+    preimage.extend_from_slice(creator.as_bytes());
+    preimage.extend_from_slice(&nonce_bytes);
+
+    Address::from_slice(&keccak256(preimage)[12..])
+}
+
+/// Runs `code` until it stops, returns or fails. Execution only depends on the code,
+/// the call context and the host state, so every node reaches the same result.
+pub fn execute<H: Host>(
+    host: &mut H,
+    code: &[u8],
+    ctx: &CallContext,
+) -> Result<ExecutionResult, VmError> {
+    let jumpdests = jumpdests(code);
+    let mut stack: Vec<U256> = Vec::new();
+    let mut gas_used: u64 = 0;
+    let mut pc: usize = 0;
+
+    while pc < code.len() {
+        let opcode = code[pc];
+        gas_used += gas_cost(opcode);
+        if gas_used > ctx.gas_limit {
+            return Err(VmError::OutOfGas);
+        }
+        pc += 1;
+
+        match opcode {
+            STOP => break,
+            ADD => binary_op(&mut stack, |a, b| a.overflowing_add(b).0)?,
+            SUB => binary_op(&mut stack, |a, b| a.overflowing_sub(b).0)?,
+            MUL => binary_op(&mut stack, |a, b| a.overflowing_mul(b).0)?,
+            // Division by zero yields zero instead of failing
+            DIV => binary_op(&mut stack, |a, b| a.checked_div(b).unwrap_or_default())?,
+            MOD => binary_op(&mut stack, |a, b| a.checked_rem(b).unwrap_or_default())?,
+            LT => binary_op(&mut stack, |a, b| U256::from((a < b) as u8))?,
+            GT => binary_op(&mut stack, |a, b| U256::from((a > b) as u8))?,
+            EQ => binary_op(&mut stack, |a, b| U256::from((a == b) as u8))?,
+            ISZERO => {
+                let a = pop(&mut stack)?;
+                push(&mut stack, U256::from(a.is_zero() as u8))?;
+            }
+            PUSH1 | PUSH32 => {
+                let size = if opcode == PUSH1 { 1 } else { 32 };
+                let bytes = code
+                    .get(pc..pc + size)
+                    .ok_or(VmError::InvalidOpcode(opcode))?;
+                push(&mut stack, U256::from_big_endian(bytes))?;
+                pc += size;
+            }
+            POP => {
+                pop(&mut stack)?;
+            }
+            DUP => {
+                let a = *stack.last().ok_or(VmError::StackUnderflow)?;
+                push(&mut stack, a)?;
+            }
+            SWAP => {
+                let a = pop(&mut stack)?;
+                let b = pop(&mut stack)?;
+                push(&mut stack, a)?;
+                push(&mut stack, b)?;
+            }
+            JUMP => pc = jump_target(&jumpdests, pop(&mut stack)?)?,
+            JUMPI => {
+                let dest = pop(&mut stack)?;
+                let condition = pop(&mut stack)?;
+                if !condition.is_zero() {
+                    pc = jump_target(&jumpdests, dest)?;
+                }
+            }
+            JUMPDEST => {}
+            SLOAD => {
+                let key = pop(&mut stack)?;
+                push(&mut stack, host.storage(&ctx.contract, &key))?;
+            }
+            SSTORE => {
+                let key = pop(&mut stack)?;
+                let value = pop(&mut stack)?;
+                host.set_storage(&ctx.contract, &key, &value);
+            }
+            CALLER => push(&mut stack, address_to_word(&ctx.caller))?,
+            CALLVALUE => push(&mut stack, ctx.value)?,
+            CALLDATALOAD => {
+                let index = pop(&mut stack)?;
+                push(&mut stack, calldata_word(ctx.input, index))?;
+            }
+            SELFBALANCE => push(&mut stack, host.balance(&ctx.contract))?,
+            BALANCE => {
+                let addr = word_to_address(&pop(&mut stack)?);
+                push(&mut stack, host.balance(&addr))?;
+            }
+            TRANSFER => {
+                let to = word_to_address(&pop(&mut stack)?);
+                let amount = pop(&mut stack)?;
+                if !host.transfer(&ctx.contract, &to, &amount) {
+                    return Err(VmError::InsufficientBalance);
+                }
+            }
+            RETURN => {
+                return Ok(ExecutionResult {
+                    gas_used,
+                    output: Some(pop(&mut stack)?),
+                })
+            }
+            REVERT => return Err(VmError::Revert),
+            _ => return Err(VmError::InvalidOpcode(opcode)),
+        }
+    }
+
+    Ok(ExecutionResult {
+        gas_used,
+        output: None,
+    })
+}
+
+fn pop(stack: &mut Vec<U256>) -> Result<U256, VmError> {
+    stack.pop().ok_or(VmError::StackUnderflow)
+}
+
+fn push(stack: &mut Vec<U256>, value: U256) -> Result<(), VmError> {
+    if stack.len() >= MAX_STACK_SIZE {
+        return Err(VmError::StackOverflow);
+    }
+    stack.push(value);
+    Ok(())
+}
+
+fn binary_op(stack: &mut Vec<U256>, op: impl Fn(U256, U256) -> U256) -> Result<(), VmError> {
+    let a = pop(stack)?;
+    let b = pop(stack)?;
+    push(stack, op(a, b))
+}
+
+// Offsets of the JUMPDEST instructions, a JUMPDEST byte inside push data is not one
+fn jumpdests(code: &[u8]) -> Vec<bool> {
+    let mut valid = vec![false; code.len()];
+    let mut pc = 0;
+    while pc < code.len() {
+        match code[pc] {
+            JUMPDEST => valid[pc] = true,
+            PUSH1 => pc += 1,
+            PUSH32 => pc += 32,
+            _ => {}
+        }
+        pc += 1;
+    }
+    valid
+}
+
+fn jump_target(jumpdests: &[bool], dest: U256) -> Result<usize, VmError> {
+    if dest >= U256::from(jumpdests.len()) || !jumpdests[dest.as_usize()] {
+        return Err(VmError::InvalidJump);
+    }
+    Ok(dest.as_usize())
+}
+
+// Words past the end of the input read as zero
+fn calldata_word(input: &[u8], index: U256) -> U256 {
+    if index >= U256::from(input.len() / 32 + 1) {
+        return U256::zero();
+    }
+    let start = index.as_usize() * 32;
+    let mut word = [0u8; 32];
+    for (i, byte) in input.iter().skip(start).take(32).enumerate() {
+        word[i] = *byte;
+    }
+    U256::from_big_endian(&word)
+}
+
+fn address_to_word(addr: &Address) -> U256 {
+    U256::from_big_endian(addr.as_bytes())
+}
+
+fn word_to_address(word: &U256) -> Address {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    Address::from_slice(&bytes[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethers::types::{Address, U256};
+
+    use super::{execute, CallContext, ExecutionResult, Host, VmError};
+    use crate::vm::opcode::*;
+
+    #[derive(Default)]
+    struct TestHost {
+        storage: HashMap<U256, U256>,
+        balances: HashMap<Address, U256>,
+    }
+
+    impl Host for TestHost {
+        fn storage(&self, _contract: &Address, key: &U256) -> U256 {
+            self.storage.get(key).copied().unwrap_or_default()
+        }
+
+        fn set_storage(&mut self, _contract: &Address, key: &U256, value: &U256) {
+            self.storage.insert(*key, *value);
+        }
+
+        fn balance(&self, addr: &Address) -> U256 {
+            self.balances.get(addr).copied().unwrap_or_default()
+        }
+
+        fn transfer(&mut self, from: &Address, to: &Address, amount: &U256) -> bool {
+            let balance = self.balance(from);
+            if balance < *amount {
+                return false;
+            }
+            self.balances.insert(*from, balance - amount);
+            let received = self.balance(to) + amount;
+            self.balances.insert(*to, received);
+            true
+        }
+    }
+
+    fn run(host: &mut TestHost, code: &[u8], gas_limit: u64) -> Result<ExecutionResult, VmError> {
+        let ctx = CallContext {
+            contract: Address::from_low_u64_be(1),
+            caller: Address::from_low_u64_be(2),
+            value: U256::zero(),
+            input: &[],
+            gas_limit,
+        };
+        execute(host, code, &ctx)
+    }
+
+    fn output(code: &[u8]) -> U256 {
+        run(&mut TestHost::default(), code, 1000)
+            .unwrap()
+            .output
+            .unwrap()
+    }
+
+    #[test]
+    fn arithmetic_takes_the_top_of_the_stack_first() {
+        assert_eq!(output(&[PUSH1, 3, PUSH1, 10, SUB, RETURN]), U256::from(7));
+        assert_eq!(output(&[PUSH1, 3, PUSH1, 10, DIV, RETURN]), U256::from(3));
+        assert_eq!(output(&[PUSH1, 0, PUSH1, 10, DIV, RETURN]), U256::zero());
+        assert_eq!(output(&[PUSH1, 3, PUSH1, 10, LT, RETURN]), U256::zero());
+        assert_eq!(output(&[PUSH1, 1, PUSH1, 0, SUB, RETURN]), U256::MAX);
+    }
+
+    #[test]
+    fn jumpi_only_jumps_on_a_non_zero_condition() {
+        // Returns 2 when the jump is taken, 1 otherwise
+        let code = |condition: u8| {
+            vec![
+                PUSH1, condition, PUSH1, 8, JUMPI, PUSH1, 1, RETURN, JUMPDEST, PUSH1, 2, RETURN,
+            ]
+        };
+        assert_eq!(output(&code(1)), U256::from(2));
+        assert_eq!(output(&code(0)), U256::from(1));
+    }
+
+    #[test]
+    fn jumps_must_land_on_a_jumpdest() {
+        let mut host = TestHost::default();
+        assert!(matches!(
+            run(&mut host, &[PUSH1, 3, JUMP, STOP], 1000),
+            Err(VmError::InvalidJump)
+        ));
+        assert!(matches!(
+            run(&mut host, &[PUSH1, 200, JUMP], 1000),
+            Err(VmError::InvalidJump)
+        ));
+        // The immediate byte of a push is not an instruction
+        assert!(matches!(
+            run(&mut host, &[PUSH1, JUMPDEST, PUSH1, 1, JUMP], 1000),
+            Err(VmError::InvalidJump)
+        ));
+    }
+
+    #[test]
+    fn endless_loops_run_out_of_gas() {
+        let mut host = TestHost::default();
+        assert!(matches!(
+            run(&mut host, &[JUMPDEST, PUSH1, 0, JUMP], 10_000),
+            Err(VmError::OutOfGas)
+        ));
+    }
+
+    #[test]
+    fn gas_is_charged_per_instruction() {
+        let mut host = TestHost::default();
+        let code = [PUSH1, 5, PUSH1, 1, SSTORE, STOP];
+        assert_eq!(run(&mut host, &code, 203).unwrap().gas_used, 203);
+        assert!(matches!(run(&mut host, &code, 202), Err(VmError::OutOfGas)));
+    }
+
+    #[test]
+    fn storage_persists_across_instructions() {
+        let mut host = TestHost::default();
+        let code = [PUSH1, 5, PUSH1, 1, SSTORE, PUSH1, 1, SLOAD, RETURN];
+        assert_eq!(
+            run(&mut host, &code, 1000).unwrap().output,
+            Some(U256::from(5))
+        );
+        assert_eq!(host.storage.get(&U256::one()), Some(&U256::from(5)));
+    }
+
+    #[test]
+    fn stack_errors_are_reported() {
+        let mut host = TestHost::default();
+        assert!(matches!(
+            run(&mut host, &[ADD], 1000),
+            Err(VmError::StackUnderflow)
+        ));
+        assert!(matches!(
+            run(&mut host, &[0xee], 1000),
+            Err(VmError::InvalidOpcode(0xee))
+        ));
+        assert!(matches!(
+            run(&mut host, &[PUSH32, 1], 1000),
+            Err(VmError::InvalidOpcode(PUSH32))
+        ));
+    }
+
+    #[test]
+    fn transfer_needs_the_contract_balance() {
+        let mut host = TestHost::default();
+        let code = [PUSH1, 40, PUSH1, 7, TRANSFER, STOP];
+        assert!(matches!(
+            run(&mut host, &code, 1000),
+            Err(VmError::InsufficientBalance)
+        ));
+
+        host.balances
+            .insert(Address::from_low_u64_be(1), U256::from(100));
+        run(&mut host, &code, 1000).unwrap();
+        assert_eq!(host.balance(&Address::from_low_u64_be(7)), U256::from(40));
+    }
+}
@@ -0,0 +1,49 @@
+// Stack machine instructions, every word on the stack is a U256
+pub const STOP: u8 = 0x00;
+pub const ADD: u8 = 0x01;
+pub const SUB: u8 = 0x02;
+pub const MUL: u8 = 0x03;
+pub const DIV: u8 = 0x04;
+pub const MOD: u8 = 0x05;
+
+pub const LT: u8 = 0x10;
+pub const GT: u8 = 0x11;
+pub const EQ: u8 = 0x12;
+pub const ISZERO: u8 = 0x13;
+
+// Followed by 1 or 32 big-endian immediate bytes
+pub const PUSH1: u8 = 0x20;
+pub const PUSH32: u8 = 0x21;
+pub const POP: u8 = 0x22;
+pub const DUP: u8 = 0x23;
+pub const SWAP: u8 = 0x24;
+
+pub const JUMP: u8 = 0x30;
+pub const JUMPI: u8 = 0x31;
+pub const JUMPDEST: u8 = 0x32;
+
+pub const SLOAD: u8 = 0x40;
+pub const SSTORE: u8 = 0x41;
+
+pub const CALLER: u8 = 0x50;
+pub const CALLVALUE: u8 = 0x51;
+// Pops a word index and pushes that 32-byte word of the call input
+pub const CALLDATALOAD: u8 = 0x52;
+pub const SELFBALANCE: u8 = 0x53;
+pub const BALANCE: u8 = 0x54;
+// Pops receiver and amount and moves native coin out of the contract
+pub const TRANSFER: u8 = 0x55;
+
+pub const RETURN: u8 = 0xf0;
+pub const REVERT: u8 = 0xfd;
+
+pub fn gas_cost(opcode: u8) -> u64 {
+    match opcode {
+        SLOAD => 50,
+        SSTORE => 200,
+        BALANCE | SELFBALANCE => 20,
+        TRANSFER => 100,
+        MUL | DIV | MOD => 5,
+        _ => 1,
+    }
+}
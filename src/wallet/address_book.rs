@@ -0,0 +1,92 @@
+use std::fs;
+use std::path::Path;
+
+use ethers::types::Address;
+use serde_derive::{Deserialize, Serialize};
+
+use super::core::{read_wallet_index, WalletStoreError, ADDRESS_BOOK, KEYS_DIR};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contact {
+    label: String,
+    address: Address,
+}
+
+impl Contact {
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+}
+
+pub fn read_address_book() -> Result<Vec<Contact>, WalletStoreError> {
+    let path = Path::new(KEYS_DIR).join(ADDRESS_BOOK);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let book =
+        fs::read_to_string(path).map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    serde_json::from_str(&book).map_err(|err| WalletStoreError::IoError(err.to_string()))
+}
+
+fn write_address_book(contacts: &[Contact]) -> Result<(), WalletStoreError> {
+    fs::create_dir_all(KEYS_DIR).map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    let content = serde_json::to_string_pretty(contacts)
+        .map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    fs::write(Path::new(KEYS_DIR).join(ADDRESS_BOOK), content)
+        .map_err(|err| WalletStoreError::IoError(err.to_string()))
+}
+
+/// Labels an address so commands accept the label in its place.
+pub fn add_contact(label: &str, address: Address) -> Result<(), WalletStoreError> {
+    // A label that parses as an address could never be looked up
+    if label.trim().is_empty() || label.parse::<Address>().is_ok() {
+        return Err(WalletStoreError::InvalidInput(format!(
+            "Invalid label: {label}"
+        )));
+    }
+    let mut contacts = read_address_book()?;
+    if contacts.iter().any(|contact| contact.label == label) {
+        return Err(WalletStoreError::AlreadyExists(label.to_string()));
+    }
+    contacts.push(Contact {
+        label: label.to_string(),
+        address,
+    });
+    contacts.sort_by(|a, b| a.label.cmp(&b.label));
+    write_address_book(&contacts)
+}
+
+pub fn remove_contact(label: &str) -> Result<(), WalletStoreError> {
+    let mut contacts = read_address_book()?;
+    let count = contacts.len();
+    contacts.retain(|contact| contact.label != label);
+    if contacts.len() == count {
+        return Err(WalletStoreError::NotFound(label.to_string()));
+    }
+    write_address_book(&contacts)
+}
+
+/// Address typed by the user: a hex address, a label from the address book or the
+/// name of a local wallet, in that order.
+pub fn resolve_address(input: &str) -> Result<Address, WalletStoreError> {
+    if let Ok(address) = input.parse::<Address>() {
+        return Ok(address);
+    }
+    if let Some(contact) = read_address_book()?
+        .into_iter()
+        .find(|contact| contact.label == input)
+    {
+        return Ok(contact.address);
+    }
+    read_wallet_index()?
+        .into_iter()
+        .find(|entry| entry.name() == input)
+        .map(|entry| *entry.address())
+        .ok_or(WalletStoreError::NotFound(format!(
+            "Address or label {input}"
+        )))
+}
@@ -1,43 +1,540 @@
+use std::fmt;
 use std::fs;
-use std::io::stdin;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use ethers::signers::{Signer, Wallet, WalletError};
-use ethers::types::Address;
+use ed25519_dalek::{Signer as _, SigningKey};
+use eth_keystore::KeystoreError;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, Signature, U256};
+use ethers::utils::hash_message;
 use rand::thread_rng;
+use serde_derive::{Deserialize, Serialize};
 
-pub fn create_wallet() -> Result<Address, WalletError> {
+use crate::signature::verification::{derive_ed25519_address, KeyType, SignatureScheme};
+use crate::transaction::core::Transaction;
+use crate::utils::input::{Input, InputError};
+
+use super::hd::{derive_account, generate_mnemonic, seed_from_phrase};
+
+pub const KEYS_DIR: &str = ".keys";
+// Lists every wallet in `.keys` with its address and key type
+const WALLET_INDEX: &str = "wallets.json";
+// Labeled addresses of counterparties, see `address_book`
+pub const ADDRESS_BOOK: &str = "address_book.json";
+
+#[derive(Debug)]
+pub enum WalletStoreError {
+    IoError(String),
+    KeystoreError(String),
+    NotFound(String),
+    AlreadyExists(String),
+    InvalidInput(String),
+    InputError(InputError),
+}
+
+impl fmt::Display for WalletStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WalletStoreError::IoError(msg) | WalletStoreError::InvalidInput(msg) => {
+                write!(f, "{msg}")
+            }
+            WalletStoreError::KeystoreError(msg) => write!(f, "Keystore error: {msg}"),
+            WalletStoreError::NotFound(name) => write!(f, "{name} not found"),
+            WalletStoreError::AlreadyExists(name) => write!(f, "{name} already exists"),
+            WalletStoreError::InputError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletEntry {
+    name: String,
+    address: Address,
+    key_type: KeyType,
+    // Set when the keystore holds a BIP-39 seed instead of a single key
+    #[serde(default)]
+    hd: bool,
+    // Addresses derived from the seed so far
+    #[serde(default)]
+    accounts: Vec<DerivedAccount>,
+    // Tracked address with no key, it has no keystore file
+    #[serde(default)]
+    watch_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedAccount {
+    index: u32,
+    address: Address,
+}
+
+impl DerivedAccount {
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+}
+
+impl WalletEntry {
+    pub fn is_hd(&self) -> bool {
+        self.hd
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only
+    }
+
+    pub fn accounts(&self) -> &Vec<DerivedAccount> {
+        &self.accounts
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+}
+
+/// Decrypted key of a wallet.
+pub enum WalletKey {
+    Secp256k1(LocalWallet),
+    Ed25519(SigningKey),
+}
+
+impl WalletKey {
+    pub fn generate(key_type: KeyType) -> WalletKey {
+        match key_type {
+            KeyType::Secp256k1 => WalletKey::Secp256k1(LocalWallet::new(&mut thread_rng())),
+            KeyType::Ed25519 => WalletKey::Ed25519(SigningKey::generate(&mut thread_rng())),
+        }
+    }
+
+    pub fn from_bytes(key_type: KeyType, secret: &[u8]) -> Result<WalletKey, WalletStoreError> {
+        let invalid = || WalletStoreError::InvalidInput(String::from("Invalid private key"));
+        match key_type {
+            KeyType::Secp256k1 => LocalWallet::from_bytes(secret)
+                .map(WalletKey::Secp256k1)
+                .map_err(|_| invalid()),
+            KeyType::Ed25519 => {
+                let seed: [u8; 32] = secret.try_into().map_err(|_| invalid())?;
+                Ok(WalletKey::Ed25519(SigningKey::from_bytes(&seed)))
+            }
+        }
+    }
+
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            WalletKey::Secp256k1(_) => KeyType::Secp256k1,
+            WalletKey::Ed25519(_) => KeyType::Ed25519,
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            WalletKey::Secp256k1(wallet) => wallet.address(),
+            WalletKey::Ed25519(key) => derive_ed25519_address(&key.verifying_key().to_bytes()),
+        }
+    }
+
+    /// Signs a message the way the node recovers secp256k1 signers, as used by
+    /// multisig members. Cosignatures are recovered, so ed25519 keys can't make one.
+    pub fn sign_message(&self, msg: &str) -> Result<Signature, WalletStoreError> {
+        match self {
+            WalletKey::Secp256k1(wallet) => wallet
+                .sign_hash(hash_message(msg))
+                .map_err(|err| WalletStoreError::KeystoreError(err.to_string())),
+            WalletKey::Ed25519(_) => Err(WalletStoreError::InvalidInput(String::from(
+                "ed25519 keys can't cosign multisig transactions, members sign with secp256k1 keys",
+            ))),
+        }
+    }
+
+    /// Signs the transaction as its sender at the given nonce.
+    pub fn sign_transaction(
+        &self,
+        tx: Transaction,
+        nonce: &U256,
+    ) -> Result<Transaction, WalletStoreError> {
+        let msg = tx.signing_message(nonce);
+        match self {
+            WalletKey::Secp256k1(_) => self
+                .sign_message(&msg)
+                .map(|signature| tx.with_signature(signature)),
+            WalletKey::Ed25519(key) => Ok(tx.with_scheme(SignatureScheme::Ed25519 {
+                public_key: key.verifying_key().to_bytes().to_vec(),
+                signature: key.sign(msg.as_bytes()).to_bytes().to_vec(),
+            })),
+        }
+    }
+
+    // Both key types are stored as a 32 byte secret
+    fn secret(&self) -> Vec<u8> {
+        match self {
+            WalletKey::Secp256k1(wallet) => wallet.signer().to_bytes().to_vec(),
+            WalletKey::Ed25519(key) => key.to_bytes().to_vec(),
+        }
+    }
+}
+
+pub fn read_wallet_index() -> Result<Vec<WalletEntry>, WalletStoreError> {
+    let path = Path::new(KEYS_DIR).join(WALLET_INDEX);
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let index =
+        fs::read_to_string(path).map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    serde_json::from_str(&index).map_err(|err| WalletStoreError::IoError(err.to_string()))
+}
+
+fn record_wallet(entry: WalletEntry) -> Result<(), WalletStoreError> {
+    let mut index = read_wallet_index()?;
+    index.retain(|known| known.name != entry.name);
+    index.push(entry);
+    let content = serde_json::to_string_pretty(&index)
+        .map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    fs::write(Path::new(KEYS_DIR).join(WALLET_INDEX), content)
+        .map_err(|err| WalletStoreError::IoError(err.to_string()))
+}
+
+fn keystore_path(name: &str) -> PathBuf {
+    Path::new(KEYS_DIR).join(name)
+}
+
+fn existing_keystore(name: &str) -> Result<PathBuf, WalletStoreError> {
+    let path = keystore_path(name);
+    if !path.is_file() {
+        if find_wallet(name)?.is_some_and(|entry| entry.watch_only) {
+            return Err(WalletStoreError::InvalidInput(format!(
+                "{name} is a watch-only wallet"
+            )));
+        }
+        return Err(WalletStoreError::NotFound(name.to_string()));
+    }
+    Ok(path)
+}
+
+fn check_new_name(name: &str) -> Result<(), WalletStoreError> {
+    if name.is_empty()
+        || name == WALLET_INDEX
+        || name == ADDRESS_BOOK
+        || name.contains(['/', '\\'])
+        || name.starts_with('.')
+    {
+        return Err(WalletStoreError::InvalidInput(format!(
+            "Invalid wallet name: {name}"
+        )));
+    }
+    if keystore_path(name).exists() || find_wallet(name)?.is_some() {
+        return Err(WalletStoreError::AlreadyExists(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Password of an existing wallet, from `--password-file` or the terminal.
+pub fn ask_password(input: &Input) -> Result<String, WalletStoreError> {
+    input
+        .password("password", "Wallet password: ")
+        .map_err(WalletStoreError::InputError)
+}
+
+fn ask_new_password(input: &Input, key: &str) -> Result<String, WalletStoreError> {
+    input
+        .new_password(key)
+        .map_err(WalletStoreError::InputError)
+}
+
+fn ask_wallet_name(input: &Input) -> Result<String, WalletStoreError> {
+    let name = input
+        .text("wallet-name", "Wallet name: ")
+        .map_err(WalletStoreError::InputError)?;
+    Ok(name.trim().to_lowercase())
+}
+
+pub fn find_wallet(name: &str) -> Result<Option<WalletEntry>, WalletStoreError> {
+    Ok(read_wallet_index()?
+        .into_iter()
+        .find(|entry| entry.name == name))
+}
+
+fn encrypt_secret(name: &str, secret: &[u8], password: &str) -> Result<(), WalletStoreError> {
+    fs::create_dir_all(KEYS_DIR).map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    eth_keystore::encrypt_key(KEYS_DIR, &mut thread_rng(), secret, password, Some(name))
+        .map(|_| ())
+        .map_err(|err| WalletStoreError::KeystoreError(err.to_string()))
+}
+
+// Wallets created before passwords were trimmed were encrypted with the trailing
+// newline, so that is tried as well
+fn decrypt_secret(name: &str, password: &str) -> Result<Vec<u8>, WalletStoreError> {
+    let path = existing_keystore(name)?;
+    eth_keystore::decrypt_key(&path, password)
+        .or_else(|_| eth_keystore::decrypt_key(&path, format!("{password}\n")))
+        .map_err(|err| match err {
+            KeystoreError::MacMismatch => {
+                WalletStoreError::InvalidInput(String::from("Wrong password"))
+            }
+            err => WalletStoreError::KeystoreError(err.to_string()),
+        })
+}
+
+// Encrypts the key into `.keys/<name>` and records it in the index
+fn store_key(name: &str, key: &WalletKey, password: &str) -> Result<Address, WalletStoreError> {
+    encrypt_secret(name, &key.secret(), password)?;
+    let address = key.address();
+    record_wallet(WalletEntry {
+        name: name.to_string(),
+        address,
+        key_type: key.key_type(),
+        hd: false,
+        accounts: vec![],
+        watch_only: false,
+    })?;
+    Ok(address)
+}
+
+/// Decrypts the wallet's key, the first account for HD wallets.
+pub fn unlock_wallet(name: &str, password: &str) -> Result<WalletKey, WalletStoreError> {
+    unlock_account(name, password, 0)
+}
+
+/// Decrypts the key of account `index`, only HD wallets have more than one.
+pub fn unlock_account(
+    name: &str,
+    password: &str,
+    index: u32,
+) -> Result<WalletKey, WalletStoreError> {
+    let secret = decrypt_secret(name, password)?;
+    match find_wallet(name)? {
+        Some(entry) if entry.hd => Ok(WalletKey::Secp256k1(derive_account(&secret, index)?)),
+        _ if index != 0 => Err(WalletStoreError::InvalidInput(format!(
+            "{name} is not an HD wallet"
+        ))),
+        entry => WalletKey::from_bytes(
+            entry.map(|entry| entry.key_type).unwrap_or_default(),
+            &secret,
+        ),
+    }
+}
+
+pub fn create_wallet(key_type: KeyType, input: &Input) -> Result<Address, WalletStoreError> {
     println!("Creating wallet...");
 
-    println!("Wallet name: ");
-    let mut buf = String::new();
-    let _ = stdin()
-        .read_line(&mut buf)
-        .map_err(|_| "stdin: Failed to read password");
-
-    let uuid = buf.to_lowercase();
-    let uuid = uuid.as_str();
-
-    println!("Wallet password: ");
-    let mut buf = String::new();
-    let _ = stdin()
-        .read_line(&mut buf)
-        .map_err(|_| "stdin: Failed to read password");
-
-    let wallet_password = buf.as_bytes();
-    println!("Do not forget your password!");
-    match fs::create_dir(".keys") {
-        Ok(_) => (),
-        Err(_) => (),
-    }
-    let dir = Path::new(".keys");
-    let res = Wallet::new_keystore(&dir, &mut thread_rng(), &wallet_password, Some(uuid));
-    match res {
-        Ok((wallet, _)) => {
-            let addr = wallet.address();
-            println!("Wallet saved to .keys/{uuid}");
-            return Ok(addr);
-        }
-        Err(e) => return Err(e),
+    let uuid = ask_wallet_name(input)?;
+    check_new_name(&uuid)?;
+    let wallet_password = ask_new_password(input, "password")?;
+
+    let addr = store_key(&uuid, &WalletKey::generate(key_type), &wallet_password)?;
+    println!("Wallet saved to .keys/{uuid}");
+    Ok(addr)
+}
+
+/// Creates an HD wallet from a new mnemonic, or restores one from a given phrase.
+/// Only the seed is kept, encrypted like any other key.
+pub fn create_hd_wallet(word_count: usize, input: &Input) -> Result<Address, WalletStoreError> {
+    println!("Creating HD wallet...");
+
+    let uuid = ask_wallet_name(input)?;
+    check_new_name(&uuid)?;
+
+    let mut phrase = input
+        .optional_text(
+            "mnemonic",
+            "Mnemonic to restore (leave empty to generate a new one): ",
+        )
+        .map_err(WalletStoreError::InputError)?;
+    if phrase.trim().is_empty() {
+        phrase = generate_mnemonic(word_count)?;
+        println!("Write down your mnemonic, it is the only backup of this wallet:");
+        println!("{phrase}");
+    }
+    let seed = seed_from_phrase(&phrase)?;
+    let wallet_password = ask_new_password(input, "password")?;
+
+    let address = derive_account(&seed, 0)?.address();
+    encrypt_secret(&uuid, &seed, &wallet_password)?;
+    record_wallet(WalletEntry {
+        name: uuid.clone(),
+        address,
+        key_type: KeyType::Secp256k1,
+        hd: true,
+        accounts: vec![DerivedAccount { index: 0, address }],
+        watch_only: false,
+    })?;
+    println!("Wallet saved to .keys/{uuid}");
+    Ok(address)
+}
+
+/// Derives the address of account `index` of an HD wallet and remembers it.
+pub fn derive_wallet_account(
+    name: &str,
+    index: u32,
+    input: &Input,
+) -> Result<Address, WalletStoreError> {
+    let mut entry = match find_wallet(name)? {
+        Some(entry) if entry.hd => entry,
+        Some(_) => {
+            return Err(WalletStoreError::InvalidInput(format!(
+                "{name} is not an HD wallet"
+            )))
+        }
+        None => return Err(WalletStoreError::NotFound(name.to_string())),
+    };
+    let password = ask_password(input)?;
+    let address = unlock_account(name, &password, index)?.address();
+    if !entry.accounts.iter().any(|account| account.index == index) {
+        entry.accounts.push(DerivedAccount { index, address });
+        entry.accounts.sort_by_key(|account| account.index);
+        record_wallet(entry)?;
+    }
+    Ok(address)
+}
+
+/// Every keystore in `.keys` by name, with its index entry when the address is known.
+pub fn list_wallets() -> Result<Vec<(String, Option<WalletEntry>)>, WalletStoreError> {
+    let index = read_wallet_index()?;
+    let mut wallets = vec![];
+    if let Ok(dir) = fs::read_dir(KEYS_DIR) {
+        for file in dir.flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            if name == WALLET_INDEX || name == ADDRESS_BOOK || !file.path().is_file() {
+                continue;
+            }
+            let entry = index.iter().find(|entry| entry.name == name).cloned();
+            wallets.push((name, entry));
+        }
+    }
+    for entry in index.into_iter().filter(|entry| entry.watch_only) {
+        wallets.push((entry.name.clone(), Some(entry)));
+    }
+    wallets.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(wallets)
+}
+
+/// Tracks an address without its key, for its balance and history only.
+pub fn add_watch_only_wallet(name: &str, address: Address) -> Result<(), WalletStoreError> {
+    check_new_name(name)?;
+    fs::create_dir_all(KEYS_DIR).map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+    record_wallet(WalletEntry {
+        name: name.to_string(),
+        address,
+        key_type: KeyType::default(),
+        hd: false,
+        accounts: vec![],
+        watch_only: true,
+    })
+}
+
+/// Imports a wallet from an existing keystore file, or from a hex private key when
+/// `keystore` is `None`.
+pub fn import_wallet(
+    name: &str,
+    keystore: Option<&str>,
+    key_type: KeyType,
+    input: &Input,
+) -> Result<Address, WalletStoreError> {
+    check_new_name(name)?;
+    match keystore {
+        Some(path) => {
+            let password = input
+                .password("password", "Keystore password: ")
+                .map_err(WalletStoreError::InputError)?;
+            let secret = eth_keystore::decrypt_key(path, &password)
+                .map_err(|err| WalletStoreError::KeystoreError(err.to_string()))?;
+            let key = WalletKey::from_bytes(key_type, &secret)?;
+
+            // The keystore keeps its own encryption and password
+            fs::create_dir_all(KEYS_DIR)
+                .map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+            fs::copy(path, keystore_path(name))
+                .map_err(|err| WalletStoreError::IoError(err.to_string()))?;
+            let address = key.address();
+            record_wallet(WalletEntry {
+                name: name.to_string(),
+                address,
+                key_type,
+                hd: false,
+                accounts: vec![],
+                watch_only: false,
+            })?;
+            Ok(address)
+        }
+        None => {
+            let hex_key = input
+                .password("private-key", "Private key (hex): ")
+                .map_err(WalletStoreError::InputError)?;
+            let secret = hex::decode(hex_key.trim().trim_start_matches("0x"))
+                .map_err(|_| WalletStoreError::InvalidInput(String::from("Invalid private key")))?;
+            let key = WalletKey::from_bytes(key_type, &secret)?;
+            let password = ask_new_password(input, "password")?;
+            store_key(name, &key, &password)
+        }
     }
 }
+
+/// The encrypted keystore JSON, or the decrypted private key in hex when `private_key`.
+pub fn export_wallet(
+    name: &str,
+    private_key: bool,
+    input: &Input,
+) -> Result<String, WalletStoreError> {
+    let path = existing_keystore(name)?;
+    if private_key {
+        let password = ask_password(input)?;
+        let key = unlock_wallet(name, &password)?;
+        return Ok(format!("0x{}", hex::encode(key.secret())));
+    }
+    fs::read_to_string(path).map_err(|err| WalletStoreError::IoError(err.to_string()))
+}
+
+/// Address of a wallet. Wallets missing from the index are unlocked once to find it.
+pub fn wallet_address(name: &str, input: &Input) -> Result<Address, WalletStoreError> {
+    if let Some(entry) = find_wallet(name)? {
+        return Ok(entry.address);
+    }
+    existing_keystore(name)?;
+    let password = ask_password(input)?;
+    let key = unlock_wallet(name, &password)?;
+    let address = key.address();
+    record_wallet(WalletEntry {
+        name: name.to_string(),
+        address,
+        key_type: key.key_type(),
+        hd: false,
+        accounts: vec![],
+        watch_only: false,
+    })?;
+    Ok(address)
+}
+
+/// Every address of a wallet: the derived accounts of an HD wallet, or its only one.
+pub fn wallet_addresses(name: &str, input: &Input) -> Result<Vec<Address>, WalletStoreError> {
+    match find_wallet(name)? {
+        Some(entry) if entry.hd => Ok(entry
+            .accounts
+            .iter()
+            .map(|account| account.address)
+            .collect()),
+        _ => Ok(vec![wallet_address(name, input)?]),
+    }
+}
+
+/// Re-encrypts the wallet's keystore under a new password.
+pub fn change_password(name: &str, input: &Input) -> Result<(), WalletStoreError> {
+    existing_keystore(name)?;
+    let password = input
+        .password("password", "Current password: ")
+        .map_err(WalletStoreError::InputError)?;
+    let secret = decrypt_secret(name, &password)?;
+    println!("New password");
+    let new_password = ask_new_password(input, "new-password")?;
+    encrypt_secret(name, &secret, &new_password)
+}
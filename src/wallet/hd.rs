@@ -0,0 +1,47 @@
+use coins_bip32::xkeys::XPriv;
+use ethers::signers::{
+    coins_bip39::{English, Mnemonic},
+    LocalWallet,
+};
+use rand::thread_rng;
+
+use super::core::WalletStoreError;
+
+// BIP-44 path of an account, addresses are Ethereum-style so its coin type is reused
+const DERIVATION_PATH_PREFIX: &str = "m/44'/60'/0'/0/";
+pub const MNEMONIC_WORD_COUNTS: [usize; 2] = [12, 24];
+
+pub fn generate_mnemonic(word_count: usize) -> Result<String, WalletStoreError> {
+    if !MNEMONIC_WORD_COUNTS.contains(&word_count) {
+        return Err(WalletStoreError::InvalidInput(format!(
+            "Mnemonics have 12 or 24 words, not {word_count}"
+        )));
+    }
+    Mnemonic::<English>::new_with_count(&mut thread_rng(), word_count)
+        .map(|mnemonic| mnemonic.to_phrase())
+        .map_err(|err| WalletStoreError::KeystoreError(err.to_string()))
+}
+
+/// BIP-39 seed of the phrase, without passphrase.
+pub fn seed_from_phrase(phrase: &str) -> Result<Vec<u8>, WalletStoreError> {
+    let words = phrase.split_whitespace().collect::<Vec<&str>>();
+    if !MNEMONIC_WORD_COUNTS.contains(&words.len()) {
+        return Err(WalletStoreError::InvalidInput(String::from(
+            "Mnemonics have 12 or 24 words",
+        )));
+    }
+    Mnemonic::<English>::new_from_phrase(&words.join(" "))
+        .and_then(|mnemonic| mnemonic.to_seed(None))
+        .map(|seed| seed.to_vec())
+        .map_err(|_| WalletStoreError::InvalidInput(String::from("Invalid mnemonic")))
+}
+
+/// Key of the account at `index` along the BIP-44 path.
+pub fn derive_account(seed: &[u8], index: u32) -> Result<LocalWallet, WalletStoreError> {
+    let key = XPriv::root_from_seed(seed, None)
+        .and_then(|root| root.derive_path(format!("{DERIVATION_PATH_PREFIX}{index}").as_str()))
+        .map_err(|err| WalletStoreError::KeystoreError(err.to_string()))?;
+    let signing_key: &coins_bip32::ecdsa::SigningKey = key.as_ref();
+    LocalWallet::from_bytes(&signing_key.to_bytes())
+        .map_err(|err| WalletStoreError::KeystoreError(err.to_string()))
+}
@@ -1 +1,3 @@
+pub mod address_book;
 pub mod core;
+pub mod hd;